@@ -0,0 +1,418 @@
+//! Size breakdown of a compiled `app_bg.wasm`, in the spirit of `twiggy top` and
+//! `twiggy dominators`.
+//!
+//! Function names come from the wasm `name` section, which release builds keep. `wasm-opt`
+//! strips it, so apps built with the `size` level only show function indices.
+
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use rustc_demangle::demangle;
+use serde::Serialize;
+use wasmparser::{
+    BinaryReaderError, ElementItems, ExternalKind, KnownCustom, Name, Operator, Parser, Payload,
+    TypeRef,
+};
+
+/// Functions listed in [`SizeReport::functions`].
+pub const TOP_FUNCTIONS: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub struct SizeReport {
+    /// Size of the whole module in bytes.
+    pub total_size: u64,
+    /// The largest functions by shallow size, at most [`TOP_FUNCTIONS`].
+    pub functions: Vec<FunctionSize>,
+    /// Function sizes summed by the crate they come from, largest first.
+    pub crates: Vec<CrateSize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionSize {
+    pub name: String,
+    /// Size of the function body itself.
+    pub shallow_size: u64,
+    /// Size of the function and everything only reachable through it, i.e. what removing it
+    /// would save.
+    pub retained_size: u64,
+    /// `shallow_size` as a percentage of the module size.
+    pub percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CrateSize {
+    pub name: String,
+    pub size: u64,
+    pub percent: f64,
+    pub functions: usize,
+}
+
+/// What the analysis needs to know about a function.
+struct Function {
+    name: Option<String>,
+    size: u64,
+    calls: Vec<u32>,
+}
+
+/// Breaks the size of `wasm` down by function and crate.
+pub fn analyze(wasm: &[u8]) -> Result<SizeReport, BinaryReaderError> {
+    let mut functions = Vec::new();
+    let mut imported = 0;
+    let mut roots = Vec::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(reader) => {
+                for import in reader.into_imports() {
+                    if let TypeRef::Func(_) = import?.ty {
+                        imported += 1;
+                        functions.push(Function {
+                            name: None,
+                            size: 0,
+                            calls: Vec::new(),
+                        });
+                    }
+                }
+            }
+            Payload::ExportSection(reader) => {
+                for export in reader {
+                    let export = export?;
+                    if export.kind == ExternalKind::Func {
+                        roots.push(export.index);
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => roots.push(func),
+            // Anything in a table may be called indirectly, so it is kept alive by the table
+            Payload::ElementSection(reader) => {
+                for element in reader {
+                    match element?.items {
+                        ElementItems::Functions(indices) => {
+                            for index in indices {
+                                roots.push(index?);
+                            }
+                        }
+                        ElementItems::Expressions(_, exprs) => {
+                            for expr in exprs {
+                                for op in expr?.get_operators_reader() {
+                                    if let Operator::RefFunc { function_index } = op? {
+                                        roots.push(function_index);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut calls = Vec::new();
+                for op in body.get_operators_reader()? {
+                    match op? {
+                        Operator::Call { function_index }
+                        | Operator::ReturnCall { function_index }
+                        | Operator::RefFunc { function_index } => calls.push(function_index),
+                        _ => {}
+                    }
+                }
+                functions.push(Function {
+                    name: None,
+                    size: body.range().len() as u64,
+                    calls,
+                });
+            }
+            Payload::CustomSection(reader) => {
+                let KnownCustom::Name(names) = reader.as_known() else {
+                    continue;
+                };
+                for name in names {
+                    let Name::Function(map) = name? else {
+                        continue;
+                    };
+                    for naming in map {
+                        let naming = naming?;
+                        if let Some(function) = functions.get_mut(naming.index as usize) {
+                            function.name = Some(format!("{:#}", demangle(naming.name)));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let total_size = wasm.len() as u64;
+    let percent = |size: u64| size as f64 * 100.0 / total_size.max(1) as f64;
+    let retained = retained_sizes(&functions, &roots);
+    let name = |index: usize, function: &Function| {
+        function
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("func[{index}]"))
+    };
+
+    let mut crates = BTreeMap::<String, (u64, usize)>::new();
+    for function in &functions[imported..] {
+        let name = function.name.as_deref().map_or("unknown", crate_of);
+        let entry = crates.entry(name.to_string()).or_default();
+        entry.0 += function.size;
+        entry.1 += 1;
+    }
+    let mut crates: Vec<_> = crates
+        .into_iter()
+        .map(|(name, (size, functions))| CrateSize {
+            name,
+            size,
+            percent: percent(size),
+            functions,
+        })
+        .collect();
+    crates.sort_by_key(|it| Reverse(it.size));
+
+    let mut top: Vec<_> = functions.iter().enumerate().skip(imported).collect();
+    top.sort_by_key(|(_, it)| Reverse(it.size));
+    let functions = top
+        .into_iter()
+        .take(TOP_FUNCTIONS)
+        .map(|(index, function)| FunctionSize {
+            name: name(index, function),
+            shallow_size: function.size,
+            retained_size: retained[index],
+            percent: percent(function.size),
+        })
+        .collect();
+
+    Ok(SizeReport {
+        total_size,
+        functions,
+        crates,
+    })
+}
+
+/// The crate a demangled function name belongs to, e.g. `yew` for
+/// `<yew::html::Html as core::fmt::Debug>::fmt`.
+fn crate_of(name: &str) -> &str {
+    let path = name.trim_start_matches(['<', '&', '*']);
+    let path = path
+        .strip_prefix("mut ")
+        .or_else(|| path.strip_prefix("dyn "))
+        .or_else(|| path.strip_prefix("const "))
+        .unwrap_or(path);
+    match path.split_once("::") {
+        Some((krate, _)) if !krate.contains(['<', ' ', '(']) => krate,
+        // wasm-bindgen's generated shims are not mangled Rust paths
+        _ if path.starts_with("__wbg") || path.starts_with("__wbindgen") => "wasm-bindgen",
+        _ => "unknown",
+    }
+}
+
+/// Retained size of every function: its own size plus the sizes of all functions it
+/// dominates in the call graph.
+///
+/// Dominators are computed with the iterative algorithm from Cooper, Harvey and Kennedy, "A
+/// Simple, Fast Dominance Algorithm", over a graph with a virtual root calling every export,
+/// table entry and the start function. Functions not reachable from there (dead code the
+/// linker kept) hang off the root directly.
+fn retained_sizes(functions: &[Function], roots: &[u32]) -> Vec<u64> {
+    let root = functions.len();
+    let valid = |index: &u32| (*index as usize) < functions.len();
+    let mut successors: Vec<Vec<usize>> = functions
+        .iter()
+        .map(|it| {
+            it.calls
+                .iter()
+                .filter(|it| valid(it))
+                .map(|it| *it as usize)
+                .collect()
+        })
+        .collect();
+    successors.push(
+        roots
+            .iter()
+            .filter(|it| valid(it))
+            .map(|it| *it as usize)
+            .collect(),
+    );
+
+    // Postorder of a depth first search from the root
+    let mut postorder = Vec::with_capacity(root + 1);
+    let mut visited = vec![false; root + 1];
+    let dfs = |start: usize, visited: &mut Vec<bool>, postorder: &mut Vec<usize>| {
+        let mut stack = vec![(start, 0)];
+        visited[start] = true;
+        while let Some((node, next)) = stack.last_mut() {
+            if let Some(&succ) = successors[*node].get(*next) {
+                *next += 1;
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            } else {
+                postorder.push(*node);
+                stack.pop();
+            }
+        }
+    };
+    visited[root] = true;
+    for index in roots.iter().filter(|it| valid(it)) {
+        let index = *index as usize;
+        if !visited[index] {
+            dfs(index, &mut visited, &mut postorder);
+        }
+    }
+    let mut unreachable = Vec::new();
+    for index in 0..root {
+        if !visited[index] {
+            unreachable.push(index);
+            dfs(index, &mut visited, &mut postorder);
+        }
+    }
+    successors[root].extend(unreachable);
+    postorder.push(root);
+
+    let mut order = vec![0; root + 1];
+    for (i, node) in postorder.iter().enumerate() {
+        order[*node] = i;
+    }
+    let mut predecessors = vec![Vec::new(); root + 1];
+    for (node, succs) in successors.iter().enumerate() {
+        for succ in succs {
+            predecessors[*succ].push(node);
+        }
+    }
+
+    let mut idom = vec![None; root + 1];
+    idom[root] = Some(root);
+    let intersect = |idom: &[Option<usize>], mut a: usize, mut b: usize| {
+        while a != b {
+            while order[a] < order[b] {
+                a = idom[a].expect("processed nodes have a dominator");
+            }
+            while order[b] < order[a] {
+                b = idom[b].expect("processed nodes have a dominator");
+            }
+        }
+        a
+    };
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in postorder.iter().rev().skip(1) {
+            let mut processed = predecessors[node].iter().filter(|it| idom[**it].is_some());
+            let Some(&first) = processed.next() else {
+                continue;
+            };
+            let new_idom = processed.fold(first, |acc, pred| intersect(&idom, acc, *pred));
+            if idom[node] != Some(new_idom) {
+                idom[node] = Some(new_idom);
+                changed = true;
+            }
+        }
+    }
+
+    // Every node comes before its dominators in postorder, so sizes bubble up in one pass
+    let mut retained: Vec<u64> = functions.iter().map(|it| it.size).collect();
+    retained.push(0);
+    for &node in &postorder[..root] {
+        let parent = idom[node].expect("every node is reachable from the root");
+        retained[parent] += retained[node];
+    }
+    retained.truncate(root);
+    retained
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module() -> Vec<u8> {
+        wat::parse_str(
+            r#"
+            (module
+                (import "wbg" "__wbg_log" (func $log))
+                (table 1 funcref)
+                (elem (i32.const 0) $indirect)
+                (func $app::main (export "main")
+                    call $app::shared
+                    call $app::only_main)
+                (func $app::only_main
+                    call $alloc::helper
+                    nop nop nop nop)
+                (func $app::shared
+                    call $log)
+                (func $alloc::helper
+                    nop nop nop nop nop nop nop nop)
+                (func $indirect
+                    call $app::shared)
+                (func $dead
+                    nop nop)
+            )
+            "#,
+        )
+        .unwrap()
+    }
+
+    fn function<'a>(report: &'a SizeReport, name: &str) -> &'a FunctionSize {
+        report.functions.iter().find(|it| it.name == name).unwrap()
+    }
+
+    #[test]
+    fn reports_functions_by_shallow_size() {
+        let wasm = module();
+        let report = analyze(&wasm).unwrap();
+        assert_eq!(report.total_size, wasm.len() as u64);
+        assert_eq!(report.functions.len(), 6);
+        assert_eq!(report.functions[0].name, "alloc::helper");
+        assert!(
+            report
+                .functions
+                .windows(2)
+                .all(|it| it[0].shallow_size >= it[1].shallow_size)
+        );
+    }
+
+    #[test]
+    fn retained_size_includes_dominated_functions() {
+        let report = analyze(&module()).unwrap();
+        let main = function(&report, "app::main");
+        let only_main = function(&report, "app::only_main");
+        let helper = function(&report, "alloc::helper");
+        let shared = function(&report, "app::shared");
+
+        assert_eq!(
+            only_main.retained_size,
+            only_main.shallow_size + helper.shallow_size
+        );
+        // `shared` is also called from the table, so `main` does not dominate it
+        assert_eq!(
+            main.retained_size,
+            main.shallow_size + only_main.retained_size
+        );
+        assert_eq!(shared.retained_size, shared.shallow_size);
+        let dead = function(&report, "dead");
+        assert_eq!(dead.retained_size, dead.shallow_size);
+    }
+
+    #[test]
+    fn rolls_up_crates() {
+        let report = analyze(&module()).unwrap();
+        let app = report.crates.iter().find(|it| it.name == "app").unwrap();
+        assert_eq!(app.functions, 3);
+        let alloc = report.crates.iter().find(|it| it.name == "alloc").unwrap();
+        assert_eq!(alloc.functions, 1);
+    }
+
+    #[test]
+    fn crate_of_demangled_names() {
+        assert_eq!(crate_of("yew::html::render"), "yew");
+        assert_eq!(
+            crate_of("<yew::html::Html as core::fmt::Debug>::fmt"),
+            "yew"
+        );
+        assert_eq!(
+            crate_of("<&mut serde_json::de::Deserializer<R>>::parse"),
+            "serde_json"
+        );
+        assert_eq!(crate_of("__wbindgen_malloc"), "wasm-bindgen");
+        assert_eq!(crate_of("memcpy"), "unknown");
+    }
+}
@@ -0,0 +1,420 @@
+use std::io;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use axum::extract::{Path, State};
+use axum::http::HeaderValue;
+use axum::http::header::{CACHE_CONTROL, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tracing::{debug, error, warn};
+
+use crate::errors::ApiError;
+use crate::storage::Bucket;
+
+pub const INDEX_HTML: &str = "index.html";
+pub const APP_JS: &str = "app.js";
+pub const APP_WASM: &str = "app_bg.wasm";
+
+/// Files an artifact consists of, with the MIME type they are served as.
+const FILES: [(&str, &str); 3] = [
+    (INDEX_HTML, "text/html; charset=utf-8"),
+    (APP_JS, "text/javascript; charset=utf-8"),
+    (APP_WASM, "application/wasm"),
+];
+
+/// Artifacts are addressed by their inputs, so a given URL always serves the same bytes.
+const IMMUTABLE: HeaderValue = HeaderValue::from_static("public, max-age=31536000, immutable");
+
+/// Compiled apps on disk, one directory per artifact id, and in a bucket if one is configured.
+///
+/// The id is derived from the compilation inputs, so re-running unchanged code (e.g. the
+/// default snippet) is served from here without queueing a build. The disk is this instance's
+/// own, so with several instances (or after scaling to zero) a request for an artifact can
+/// reach one that did not build it. Such requests are answered from the bucket, which also
+/// refills the disk.
+pub struct ArtifactStore {
+    dir: PathBuf,
+    capacity: usize,
+    bucket: Option<Bucket>,
+    /// Numbers scratch directories, so concurrent writes of one artifact do not share one.
+    scratch: AtomicU64,
+}
+
+impl ArtifactStore {
+    pub async fn open(dir: PathBuf, capacity: usize, bucket: Option<Bucket>) -> io::Result<Self> {
+        fs::create_dir_all(&dir).await?;
+        // scratch directories left by a previous process
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_name().to_string_lossy().ends_with(".tmp") {
+                fs::remove_dir_all(entry.path()).await?;
+            }
+        }
+        Ok(Self {
+            dir,
+            capacity,
+            bucket,
+            scratch: AtomicU64::new(0),
+        })
+    }
+
+    /// Derives the id of the artifact built from `inputs`, everything that affects the output,
+    /// including the template's [fingerprint](crate::compile::fingerprint). SHA-256 over their
+    /// JSON, so the id stays the same across builds of the backend.
+    pub fn id(inputs: &impl Serialize) -> String {
+        let json = serde_json::to_vec(inputs).expect("artifact inputs serialize to JSON");
+        format!("{:x}", Sha256::digest(json))
+    }
+
+    fn is_valid_id(id: &str) -> bool {
+        id.len() == 64 && id.bytes().all(|it| matches!(it, b'0'..=b'9' | b'a'..=b'f'))
+    }
+
+    /// Size of the stored artifact's wasm file, `None` if there is no such artifact.
+    pub async fn wasm_size(&self, id: &str) -> Option<u64> {
+        let path = self.dir.join(id).join(APP_WASM);
+        if let Ok(metadata) = fs::metadata(&path).await {
+            return Some(metadata.len());
+        }
+        match self.restore(id).await {
+            Ok(true) => Some(fs::metadata(&path).await.ok()?.len()),
+            Ok(false) => None,
+            Err(e) => {
+                warn!(?e, id, "failed to restore artifact");
+                None
+            }
+        }
+    }
+
+    pub async fn read(&self, id: &str, file: &str) -> io::Result<Vec<u8>> {
+        let path = self.dir.join(id).join(file);
+        match fs::read(&path).await {
+            Err(e) if e.kind() == io::ErrorKind::NotFound && self.restore(id).await? => {
+                fs::read(&path).await
+            }
+            result => result,
+        }
+    }
+
+    /// Stores a complete artifact, and uploads it to the bucket. Files are written to a scratch
+    /// directory first and moved in place at once, so readers never see a partial artifact.
+    /// A failed upload is only logged: this instance serves the artifact either way, and
+    /// failing the run would have the client compile it all over again.
+    pub async fn store(&self, id: &str, files: &[(&str, &[u8])]) -> io::Result<()> {
+        self.install(id, files).await?;
+        debug!(id, "stored artifact");
+        if let Some(bucket) = &self.bucket {
+            for (name, contents) in files {
+                if let Err(e) = bucket
+                    .put(&object(id, name), content_type(name), contents.to_vec())
+                    .await
+                {
+                    warn!(
+                        ?e,
+                        id, "failed to upload artifact, only this instance serves it"
+                    );
+                    break;
+                }
+            }
+        }
+
+        if let Err(e) = self.evict().await {
+            warn!(?e, "failed to evict old artifacts");
+        }
+        Ok(())
+    }
+
+    /// Copies an artifact from the bucket to disk. Returns `false` if there is no bucket or it
+    /// does not have the artifact.
+    async fn restore(&self, id: &str) -> io::Result<bool> {
+        let Some(bucket) = &self.bucket else {
+            return Ok(false);
+        };
+        let mut files = Vec::with_capacity(FILES.len());
+        for (name, _) in FILES {
+            match bucket
+                .get(&object(id, name))
+                .await
+                .map_err(io::Error::other)?
+            {
+                Some(contents) => files.push((name, contents)),
+                None => return Ok(false),
+            }
+        }
+        let files: Vec<_> = files
+            .iter()
+            .map(|(name, contents)| (*name, contents.as_slice()))
+            .collect();
+        self.install(id, &files).await?;
+        debug!(id, "restored artifact from the bucket");
+        Ok(true)
+    }
+
+    /// Writes `files` to a scratch directory of their own and moves it to the artifact's. An
+    /// artifact that is already there holds the same files and is left alone, so it never
+    /// disappears while being read.
+    async fn install(&self, id: &str, files: &[(&str, &[u8])]) -> io::Result<()> {
+        let scratch = self.dir.join(format!(
+            ".{id}.{}.tmp",
+            self.scratch.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(&scratch).await?;
+        for (name, contents) in files {
+            fs::write(scratch.join(name), contents).await?;
+        }
+
+        let target = self.dir.join(id);
+        let result = if is_dir(&target).await {
+            Ok(())
+        } else {
+            fs::rename(&scratch, &target).await
+        };
+        if fs::try_exists(&scratch).await? {
+            fs::remove_dir_all(&scratch).await?;
+        }
+        match result {
+            // another write of the artifact won the race
+            Err(_) if is_dir(&target).await => Ok(()),
+            result => result,
+        }
+    }
+
+    /// Removes the least recently written artifacts beyond `capacity`.
+    async fn evict(&self) -> io::Result<()> {
+        let mut artifacts = Vec::new();
+        let mut entries = fs::read_dir(&self.dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name();
+            if !name.to_str().is_some_and(Self::is_valid_id) {
+                continue;
+            }
+            let modified = entry
+                .metadata()
+                .await?
+                .modified()
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            artifacts.push((modified, entry.path()));
+        }
+
+        if artifacts.len() <= self.capacity {
+            return Ok(());
+        }
+        artifacts.sort();
+        let excess = artifacts.len() - self.capacity;
+        for (_, path) in artifacts.into_iter().take(excess) {
+            debug!(?path, "evicting artifact");
+            fs::remove_dir_all(path).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn is_dir(path: &FsPath) -> bool {
+    fs::metadata(path).await.is_ok_and(|it| it.is_dir())
+}
+
+/// The bucket object holding `file` of artifact `id`.
+fn object(id: &str, file: &str) -> String {
+    format!("artifacts/{id}/{file}")
+}
+
+fn content_type(file: &str) -> &'static str {
+    FILES
+        .iter()
+        .find(|(name, _)| *name == file)
+        .map_or("application/octet-stream", |(_, content_type)| content_type)
+}
+
+/// Serves a single file of an artifact with the MIME type browsers expect, e.g.
+/// `application/wasm` for `WebAssembly.instantiateStreaming`.
+pub async fn serve(
+    State(store): State<Arc<ArtifactStore>>,
+    Path((id, file)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+    let content_type = FILES
+        .iter()
+        .find(|(name, _)| *name == file)
+        .map(|(_, content_type)| *content_type);
+    let (true, Some(content_type)) = (ArtifactStore::is_valid_id(&id), content_type) else {
+        return Err(ApiError::NotFound(format!("no artifact file {id}/{file}")));
+    };
+
+    let contents = match store.read(&id, &file).await {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(ApiError::NotFound(format!("no artifact file {id}/{file}")));
+        }
+        Err(e) => {
+            error!(?e, id, file, "failed to read artifact");
+            return Err(ApiError::IoError(e));
+        }
+    };
+
+    Ok((
+        [
+            (CONTENT_TYPE, HeaderValue::from_static(content_type)),
+            (CACHE_CONTROL, IMMUTABLE),
+        ],
+        contents,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use axum::Router;
+    use axum::body::Bytes;
+    use axum::extract::Query;
+    use axum::http::StatusCode;
+    use axum::routing::{get, post};
+
+    use super::*;
+    use crate::config::CacheConfig;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "playground-artifact-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    const ARTIFACT: [(&str, &[u8]); 3] = [
+        (INDEX_HTML, b"<html>"),
+        (APP_JS, b"export default 1"),
+        (APP_WASM, b"\0asm"),
+    ];
+
+    /// A Cloud Storage stand-in, uploading and downloading objects like the JSON API does.
+    async fn storage_stand_in() -> String {
+        type Objects = Arc<Mutex<HashMap<String, Bytes>>>;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route(
+                "/upload/storage/v1/b/artifacts/o",
+                post(
+                    |State(objects): State<Objects>,
+                     Query(query): Query<HashMap<String, String>>,
+                     body: Bytes| async move {
+                        let mut objects = objects.lock().unwrap();
+                        if objects.contains_key(&query["name"]) {
+                            return StatusCode::PRECONDITION_FAILED;
+                        }
+                        objects.insert(query["name"].clone(), body);
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .route(
+                "/storage/v1/b/artifacts/o/{object}",
+                get(
+                    |State(objects): State<Objects>, Path(object): Path<String>| async move {
+                        match objects.lock().unwrap().get(&object) {
+                            Some(body) => (StatusCode::OK, body.clone()),
+                            None => (StatusCode::NOT_FOUND, Bytes::new()),
+                        }
+                    },
+                ),
+            )
+            .with_state(Objects::default());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn ids_are_stable() {
+        let id = ArtifactStore::id(&("stable", 1));
+        assert_eq!(
+            id,
+            "c5c7427d9ab33a11d238f53c049897bd23e9badd608635aba9c1cc37436d7582"
+        );
+        assert!(ArtifactStore::is_valid_id(&id));
+        assert_ne!(ArtifactStore::id(&("stable", 2)), id);
+        assert!(!ArtifactStore::is_valid_id("../etc"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_stores_keep_the_artifact() {
+        let dir = scratch_dir("concurrent");
+        let store = Arc::new(ArtifactStore::open(dir.clone(), 4, None).await.unwrap());
+        let id = ArtifactStore::id(&"concurrent");
+        let mut stores = tokio::task::JoinSet::new();
+        for _ in 0..8 {
+            let (store, id) = (store.clone(), id.clone());
+            stores.spawn(async move { store.store(&id, &ARTIFACT).await });
+        }
+        while let Some(result) = stores.join_next().await {
+            result.unwrap().unwrap();
+        }
+        assert_eq!(store.read(&id, APP_JS).await.unwrap(), b"export default 1");
+        // every scratch directory is gone
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn restores_artifacts_from_the_bucket() {
+        let config = CacheConfig {
+            bucket: "artifacts".to_string(),
+            storage_url: storage_stand_in().await,
+            storage_auth: false,
+            ..CacheConfig::default()
+        };
+        let id = ArtifactStore::id(&"restored");
+        let (built, other) = (scratch_dir("built"), scratch_dir("other"));
+        let builder = ArtifactStore::open(built.clone(), 4, Bucket::open(&config).unwrap())
+            .await
+            .unwrap();
+        builder.store(&id, &ARTIFACT).await.unwrap();
+
+        // an instance that did not build it, with the bucket and without
+        let store = ArtifactStore::open(other.clone(), 4, Bucket::open(&config).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(store.wasm_size(&id).await, Some(4));
+        assert_eq!(store.read(&id, INDEX_HTML).await.unwrap(), b"<html>");
+        assert_eq!(store.wasm_size(&ArtifactStore::id(&"missing")).await, None);
+        let local = ArtifactStore::open(scratch_dir("local"), 4, None)
+            .await
+            .unwrap();
+        assert_eq!(local.wasm_size(&id).await, None);
+
+        for dir in [built, other, scratch_dir("local")] {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn stores_locally_when_the_bucket_fails() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new().fallback(|| async { StatusCode::SERVICE_UNAVAILABLE });
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let config = CacheConfig {
+            bucket: "artifacts".to_string(),
+            storage_url: format!("http://{addr}"),
+            storage_auth: false,
+            ..CacheConfig::default()
+        };
+
+        let dir = scratch_dir("failing-bucket");
+        let store = ArtifactStore::open(dir.clone(), 4, Bucket::open(&config).unwrap())
+            .await
+            .unwrap();
+        let id = ArtifactStore::id(&"failing-bucket");
+        store.store(&id, &ARTIFACT).await.unwrap();
+        assert_eq!(store.wasm_size(&id).await, Some(4));
+        assert_eq!(store.read(&id, APP_JS).await.unwrap(), b"export default 1");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::fs;
+use tokio::process::Command;
+use tracing::{debug, error};
+
+use crate::config::config;
+use crate::errors::ApiError;
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum YewVersion {
+    #[default]
+    Stable,
+    Next,
+}
+
+impl YewVersion {
+    pub const ALL: [YewVersion; 2] = [YewVersion::Stable, YewVersion::Next];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            YewVersion::Stable => "stable",
+            YewVersion::Next => "next",
+        }
+    }
+
+    /// The template crate user code is compiled in, as configured (not canonicalized).
+    pub fn app_dir(self) -> &'static Path {
+        match self {
+            YewVersion::Stable => &config().templates.stable,
+            YewVersion::Next => &config().templates.next,
+        }
+    }
+}
+
+/// How much effort goes into making the output small and fast.
+#[derive(Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum OptLevel {
+    /// Unoptimized build with debug assertions, the quickest to compile.
+    Debug,
+    #[default]
+    Release,
+    /// Release build shrunk further with `wasm-opt -Oz`.
+    Size,
+}
+
+impl OptLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            OptLevel::Debug => "debug",
+            OptLevel::Release => "release",
+            OptLevel::Size => "size",
+        }
+    }
+}
+
+/// The files trunk emits for a successful build.
+pub struct BuildOutput {
+    pub js: String,
+    pub wasm: Vec<u8>,
+}
+
+pub async fn canonical_app_dir(version: YewVersion) -> Result<PathBuf, ApiError> {
+    fs::canonicalize(version.app_dir()).await.map_err(|e| {
+        error!(?e, "failed to canonicalize app_dir path");
+        ApiError::IoError(e)
+    })
+}
+
+/// Writes `code` as the template's `main.rs` and builds it with trunk, running `wasm-opt` on
+/// the result for [`OptLevel::Size`].
+///
+/// The template directory is shared, so callers must hold a compile queue permit.
+pub async fn build(app_dir: &Path, code: &str, opt: OptLevel) -> Result<BuildOutput, ApiError> {
+    fs::write(app_dir.join("src/main.rs"), code)
+        .await
+        .map_err(|e| {
+            error!(?e, "failed to write main.rs");
+            ApiError::IoError(e)
+        })?;
+
+    let mut cmd = Command::new(&config().trunk_bin);
+    let cmd = cmd
+        .env("CARGO_TERM_COLOR", "always")
+        .arg("--color")
+        .arg("always")
+        .arg("--config")
+        .arg(app_dir.join("Trunk.toml"))
+        .arg("build")
+        .arg(format!("--release={}", opt != OptLevel::Debug))
+        .kill_on_drop(true);
+    debug!(?cmd, "running command");
+
+    let output = cmd.output().await.map_err(|e| {
+        error!(?e, "running trunk failed");
+        ApiError::IoError(e)
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let html = anstyle_svg::Term::new().render_html(&stderr);
+        return Err(ApiError::CompileFailed { html });
+    }
+
+    let dist = app_dir.join("dist");
+    if opt == OptLevel::Size {
+        wasm_opt(&dist.join("app_bg.wasm")).await?;
+    }
+    let js = fs::read_to_string(dist.join("app.js")).await.map_err(|e| {
+        error!(?e, "failed to read app.js");
+        ApiError::IoError(e)
+    })?;
+    let wasm = fs::read(dist.join("app_bg.wasm")).await.map_err(|e| {
+        error!(?e, "failed to read app_bg.wasm");
+        ApiError::IoError(e)
+    })?;
+
+    debug!(wasm_bytes = wasm.len(), "compilation successful");
+    Ok(BuildOutput { js, wasm })
+}
+
+/// Shrinks the wasm file at `path` in place.
+async fn wasm_opt(path: &Path) -> Result<(), ApiError> {
+    let mut cmd = Command::new(&config().wasm_opt_bin);
+    let cmd = cmd
+        .arg("-Oz")
+        .arg(path)
+        .arg("-o")
+        .arg(path)
+        .kill_on_drop(true);
+    debug!(?cmd, "running command");
+
+    let output = cmd.output().await.map_err(|e| {
+        error!(?e, "running wasm-opt failed");
+        ApiError::IoError(e)
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!(%stderr, "wasm-opt failed");
+        return Err(ApiError::Unknown(anyhow!(
+            "wasm-opt failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+/// What decides a build's output besides the request: the template's locked dependencies and
+/// the versions of the tools that build it, as a SHA-256 hex digest. Part of every artifact id,
+/// so artifacts built before a dependency or toolchain update are not served after it.
+pub async fn fingerprint(version: YewVersion) -> String {
+    let app_dir = version.app_dir();
+    let mut hasher = Sha256::new();
+    hasher.update(
+        fs::read(app_dir.join("Cargo.lock"))
+            .await
+            .unwrap_or_default(),
+    );
+    for bin in [
+        "rustc",
+        &config().trunk_bin,
+        &config().wasm_opt_bin,
+        &config().tailwind_bin,
+    ] {
+        // run in the template, where a rust-toolchain file picks the compiler
+        let output = Command::new(bin)
+            .arg("--version")
+            .current_dir(app_dir)
+            .output()
+            .await;
+        match output {
+            Ok(output) => hasher.update(&output.stdout),
+            Err(e) => debug!(?e, bin, "no version for the fingerprint"),
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
@@ -0,0 +1,849 @@
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use clap::Parser;
+use clap::builder::FalseyValueParser;
+use serde::{Deserialize, Serialize};
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// The loaded configuration. Only valid after [`init`] was called in `main`.
+pub fn config() -> &'static Config {
+    CONFIG.get().expect("config is loaded on startup")
+}
+
+pub fn init(config: Config) {
+    CONFIG.set(config).expect("config is only loaded once");
+}
+
+/// Backend configuration.
+///
+/// Values are resolved from lowest to highest precedence: built-in defaults, the TOML config
+/// file, environment variables and finally command line flags.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub port: u16,
+    pub trunk_bin: String,
+    /// Only needed for the `size` optimization level.
+    pub wasm_opt_bin: String,
+    /// Standalone Tailwind CSS CLI, only needed for runs with `tailwind` enabled.
+    pub tailwind_bin: String,
+    /// Only needed for formatting code.
+    pub rustfmt_bin: String,
+    pub warmup: bool,
+    pub log: LogConfig,
+    pub templates: TemplatesConfig,
+    pub compile: CompileConfig,
+    pub cors: CorsConfig,
+    pub cache: CacheConfig,
+    pub share: ShareConfig,
+    pub import: ImportConfig,
+    pub lsp: LspConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `tracing_subscriber::EnvFilter` directives.
+    pub filter: String,
+    pub ansi: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplatesConfig {
+    pub stable: PathBuf,
+    pub next: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompileConfig {
+    pub timeout_secs: u64,
+    /// Compilations running at the same time. Each template directory is shared, so anything
+    /// above 1 requires one template copy per slot.
+    pub concurrency: usize,
+    /// Requests allowed to wait for a compile slot before new ones are rejected.
+    pub max_queued: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Allowed origins. `["*"]` allows any origin.
+    pub origins: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    /// Where compiled artifacts are stored.
+    pub dir: PathBuf,
+    /// Compiled artifacts kept on disk.
+    pub capacity: usize,
+    /// Cloud Storage bucket artifacts are also uploaded to, so every instance can serve them.
+    /// Empty keeps them on this instance only.
+    pub bucket: String,
+    /// The Cloud Storage JSON API's base URL.
+    pub storage_url: String,
+    /// Authenticate with the service account token from the metadata server.
+    pub storage_auth: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShareConfig {
+    pub store: ShareStoreKind,
+    /// Where the `sqlite` and `fs` stores keep shares.
+    pub dir: PathBuf,
+    /// Largest share accepted, in bytes of JSON.
+    pub max_size: usize,
+    /// The Firestore database's `documents` URL, for the `firestore` store. There is no default,
+    /// shares go to a database the deployment names.
+    pub firestore_url: String,
+    /// Collection shares are stored in.
+    pub firestore_collection: String,
+    /// Authenticate with the service account token from the metadata server. Off for the
+    /// Firestore emulator.
+    pub firestore_auth: bool,
+    /// The old Firestore sharing's `pastes` collection, read for links to those pastes. Empty
+    /// turns that off.
+    pub legacy_pastes_url: String,
+}
+
+/// Fetching code from gists and raw URLs for `/import`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ImportConfig {
+    /// Hosts files may be fetched from. `github_api` is only used for gists and never one of them.
+    pub allowed_hosts: Vec<String>,
+    /// Gists are read from `{github_api}/gists/{id}`.
+    pub github_api: String,
+    /// Token for `github_api`, which allows far more requests than anonymous ones. Empty sends
+    /// none. Left out of `--print-config`.
+    #[serde(skip_serializing)]
+    pub github_token: String,
+    /// Largest file fetched, in bytes.
+    pub max_size: usize,
+    pub timeout_secs: u64,
+}
+
+/// rust-analyzer sessions behind `/lsp`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LspConfig {
+    pub enabled: bool,
+    pub rust_analyzer_bin: String,
+    /// Sessions running at the same time, each is a rust-analyzer process. Together with
+    /// `memory_limit_mb` this bounds the memory sessions take.
+    pub max_sessions: usize,
+    /// Sessions one client address may hold at the same time.
+    pub max_sessions_per_client: usize,
+    /// Take the client address from the last `X-Forwarded-For` entry, which is the one the
+    /// proxy in front of the backend (such as Cloud Run's) adds. Only safe behind such a proxy.
+    pub trust_forwarded_for: bool,
+    /// Sessions without a message from the editor for this long are ended.
+    pub idle_timeout_secs: u64,
+    /// Sessions whose rust-analyzer uses more resident memory are ended.
+    pub memory_limit_mb: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareStoreKind {
+    /// A SQLite database in the share directory.
+    Sqlite,
+    /// One JSON file per share.
+    Fs,
+    /// Documents in a Firestore collection, which outlive the instance.
+    Firestore,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            port: 3000,
+            trunk_bin: "trunk".to_string(),
+            wasm_opt_bin: "wasm-opt".to_string(),
+            tailwind_bin: "tailwindcss".to_string(),
+            rustfmt_bin: "rustfmt".to_string(),
+            warmup: false,
+            log: LogConfig::default(),
+            templates: TemplatesConfig::default(),
+            compile: CompileConfig::default(),
+            cors: CorsConfig::default(),
+            cache: CacheConfig::default(),
+            share: ShareConfig::default(),
+            import: ImportConfig::default(),
+            lsp: LspConfig::default(),
+        }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: "backend=trace,hyper=debug,tower_http=debug".to_string(),
+            ansi: true,
+        }
+    }
+}
+
+impl Default for TemplatesConfig {
+    fn default() -> Self {
+        Self {
+            stable: PathBuf::from("../app"),
+            next: PathBuf::from("../app-next"),
+        }
+    }
+}
+
+impl Default for CompileConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 60,
+            concurrency: 1,
+            max_queued: 16,
+        }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            origins: vec!["*".to_string()],
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            dir: std::env::temp_dir().join("yew-playground"),
+            capacity: 64,
+            bucket: String::new(),
+            storage_url: "https://storage.googleapis.com".to_string(),
+            storage_auth: true,
+        }
+    }
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            store: ShareStoreKind::Sqlite,
+            dir: PathBuf::from("shares"),
+            max_size: 256 * 1024,
+            firestore_url: String::new(),
+            firestore_collection: "shares".to_string(),
+            firestore_auth: true,
+            legacy_pastes_url:
+                "https://firestore.googleapis.com/v1/projects/pastify-app/databases/(default)/documents/pastes"
+                    .to_string(),
+        }
+    }
+}
+
+impl Default for ImportConfig {
+    fn default() -> Self {
+        Self {
+            allowed_hosts: vec![
+                "gist.githubusercontent.com".to_string(),
+                "raw.githubusercontent.com".to_string(),
+            ],
+            github_api: "https://api.github.com".to_string(),
+            github_token: String::new(),
+            max_size: 256 * 1024,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl Default for LspConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rust_analyzer_bin: "rust-analyzer".to_string(),
+            max_sessions: 2,
+            max_sessions_per_client: 1,
+            trust_forwarded_for: false,
+            idle_timeout_secs: 600,
+            memory_limit_mb: 1024,
+        }
+    }
+}
+
+/// Command line flags. Every flag can also be set through the environment variable listed in
+/// `--help`, which takes precedence over the config file.
+#[derive(Debug, Parser)]
+#[command(about = "Compiles and serves Yew apps for the playground")]
+pub struct Cli {
+    /// Path to a TOML config file
+    #[arg(long, env = "PLAYGROUND_CONFIG")]
+    config: Option<PathBuf>,
+    /// Print the resolved config as TOML and exit
+    #[arg(long)]
+    print_config: bool,
+
+    #[arg(long, env = "PORT")]
+    port: Option<u16>,
+    #[arg(long, env = "TRUNK_BIN")]
+    trunk_bin: Option<String>,
+    #[arg(long, env = "WASM_OPT_BIN")]
+    wasm_opt_bin: Option<String>,
+    #[arg(long, env = "TAILWIND_BIN")]
+    tailwind_bin: Option<String>,
+    #[arg(long, env = "RUSTFMT_BIN")]
+    rustfmt_bin: Option<String>,
+    /// Compile each template's default snippet before reporting ready
+    #[arg(
+        long,
+        env = "WARMUP",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = FalseyValueParser::new(),
+    )]
+    warmup: Option<bool>,
+    #[arg(long, env = "RUST_LOG")]
+    log_filter: Option<String>,
+    /// Disable colors in log output
+    #[arg(long, env = "NO_ANSI_LOG", value_parser = FalseyValueParser::new())]
+    no_ansi_log: bool,
+    #[arg(long, env = "APP_DIR_STABLE")]
+    app_dir_stable: Option<PathBuf>,
+    #[arg(long, env = "APP_DIR_NEXT")]
+    app_dir_next: Option<PathBuf>,
+    #[arg(long, env = "PLAYGROUND_COMPILE_TIMEOUT_SECS")]
+    compile_timeout_secs: Option<u64>,
+    #[arg(long, env = "PLAYGROUND_COMPILE_CONCURRENCY")]
+    compile_concurrency: Option<usize>,
+    #[arg(long, env = "PLAYGROUND_COMPILE_MAX_QUEUED")]
+    compile_max_queued: Option<usize>,
+    /// Allowed CORS origins, comma separated
+    #[arg(long, env = "PLAYGROUND_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+    #[arg(long, env = "PLAYGROUND_CACHE_DIR")]
+    cache_dir: Option<PathBuf>,
+    #[arg(long, env = "PLAYGROUND_CACHE_CAPACITY")]
+    cache_capacity: Option<usize>,
+    #[arg(long, env = "PLAYGROUND_CACHE_BUCKET")]
+    cache_bucket: Option<String>,
+    #[arg(long, env = "PLAYGROUND_CACHE_STORAGE_URL")]
+    cache_storage_url: Option<String>,
+    /// Authenticate to Cloud Storage with the metadata server's token
+    #[arg(
+        long,
+        env = "PLAYGROUND_CACHE_STORAGE_AUTH",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = FalseyValueParser::new(),
+    )]
+    cache_storage_auth: Option<bool>,
+    #[arg(long, env = "PLAYGROUND_SHARE_STORE")]
+    share_store: Option<ShareStoreKind>,
+    #[arg(long, env = "PLAYGROUND_SHARE_DIR")]
+    share_dir: Option<PathBuf>,
+    #[arg(long, env = "PLAYGROUND_SHARE_MAX_SIZE")]
+    share_max_size: Option<usize>,
+    #[arg(long, env = "PLAYGROUND_SHARE_FIRESTORE_URL")]
+    share_firestore_url: Option<String>,
+    #[arg(long, env = "PLAYGROUND_SHARE_FIRESTORE_COLLECTION")]
+    share_firestore_collection: Option<String>,
+    /// Authenticate to Firestore with the metadata server's token
+    #[arg(
+        long,
+        env = "PLAYGROUND_SHARE_FIRESTORE_AUTH",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = FalseyValueParser::new(),
+    )]
+    share_firestore_auth: Option<bool>,
+    /// Where old Firestore pastes are read from, empty to turn that off
+    #[arg(long, env = "PLAYGROUND_SHARE_LEGACY_PASTES_URL")]
+    share_legacy_pastes_url: Option<String>,
+    /// Hosts code may be imported from, comma separated
+    #[arg(long, env = "PLAYGROUND_IMPORT_ALLOWED_HOSTS", value_delimiter = ',')]
+    import_allowed_hosts: Option<Vec<String>>,
+    #[arg(long, env = "PLAYGROUND_IMPORT_GITHUB_API")]
+    import_github_api: Option<String>,
+    #[arg(long, env = "PLAYGROUND_IMPORT_GITHUB_TOKEN", hide_env_values = true)]
+    import_github_token: Option<String>,
+    #[arg(long, env = "PLAYGROUND_IMPORT_MAX_SIZE")]
+    import_max_size: Option<usize>,
+    #[arg(long, env = "PLAYGROUND_IMPORT_TIMEOUT_SECS")]
+    import_timeout_secs: Option<u64>,
+    /// Serve rust-analyzer sessions on `/api/lsp`
+    #[arg(
+        long,
+        env = "PLAYGROUND_LSP_ENABLED",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = FalseyValueParser::new(),
+    )]
+    lsp_enabled: Option<bool>,
+    #[arg(long, env = "RUST_ANALYZER_BIN")]
+    rust_analyzer_bin: Option<String>,
+    #[arg(long, env = "PLAYGROUND_LSP_MAX_SESSIONS")]
+    lsp_max_sessions: Option<usize>,
+    #[arg(long, env = "PLAYGROUND_LSP_MAX_SESSIONS_PER_CLIENT")]
+    lsp_max_sessions_per_client: Option<usize>,
+    /// Identify lsp clients by the last `X-Forwarded-For` entry
+    #[arg(
+        long,
+        env = "PLAYGROUND_LSP_TRUST_FORWARDED_FOR",
+        num_args = 0..=1,
+        default_missing_value = "true",
+        value_parser = FalseyValueParser::new(),
+    )]
+    lsp_trust_forwarded_for: Option<bool>,
+    #[arg(long, env = "PLAYGROUND_LSP_IDLE_TIMEOUT_SECS")]
+    lsp_idle_timeout_secs: Option<u64>,
+    #[arg(long, env = "PLAYGROUND_LSP_MEMORY_LIMIT_MB")]
+    lsp_memory_limit_mb: Option<u64>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("failed to parse config file {}: {source}", path.display())]
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+    #[error("invalid value for `{key}`: {reason}")]
+    Invalid { key: &'static str, reason: String },
+}
+
+impl Cli {
+    pub fn print_config(&self) -> bool {
+        self.print_config
+    }
+
+    /// Resolves the final config from the config file, the environment and the flags.
+    pub fn load(self) -> Result<Config, ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => {
+                let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                    path: path.clone(),
+                    source,
+                })?;
+                toml::from_str(&text).map_err(|source| ConfigError::Parse {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            None => Config::default(),
+        };
+
+        fn set<T>(target: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *target = value;
+            }
+        }
+        set(&mut config.port, self.port);
+        set(&mut config.trunk_bin, self.trunk_bin);
+        set(&mut config.wasm_opt_bin, self.wasm_opt_bin);
+        set(&mut config.tailwind_bin, self.tailwind_bin);
+        set(&mut config.rustfmt_bin, self.rustfmt_bin);
+        set(&mut config.warmup, self.warmup);
+        set(&mut config.log.filter, self.log_filter);
+        if self.no_ansi_log {
+            config.log.ansi = false;
+        }
+        set(&mut config.templates.stable, self.app_dir_stable);
+        set(&mut config.templates.next, self.app_dir_next);
+        set(&mut config.compile.timeout_secs, self.compile_timeout_secs);
+        set(&mut config.compile.concurrency, self.compile_concurrency);
+        set(&mut config.compile.max_queued, self.compile_max_queued);
+        set(&mut config.cors.origins, self.cors_origins);
+        set(&mut config.cache.dir, self.cache_dir);
+        set(&mut config.cache.capacity, self.cache_capacity);
+        set(&mut config.cache.bucket, self.cache_bucket);
+        set(&mut config.cache.storage_url, self.cache_storage_url);
+        set(&mut config.cache.storage_auth, self.cache_storage_auth);
+        set(&mut config.share.store, self.share_store);
+        set(&mut config.share.dir, self.share_dir);
+        set(&mut config.share.max_size, self.share_max_size);
+        set(&mut config.share.firestore_url, self.share_firestore_url);
+        set(
+            &mut config.share.firestore_collection,
+            self.share_firestore_collection,
+        );
+        set(&mut config.share.firestore_auth, self.share_firestore_auth);
+        set(
+            &mut config.share.legacy_pastes_url,
+            self.share_legacy_pastes_url,
+        );
+        set(&mut config.import.allowed_hosts, self.import_allowed_hosts);
+        set(&mut config.import.github_api, self.import_github_api);
+        set(&mut config.import.github_token, self.import_github_token);
+        set(&mut config.import.max_size, self.import_max_size);
+        set(&mut config.import.timeout_secs, self.import_timeout_secs);
+        set(&mut config.lsp.enabled, self.lsp_enabled);
+        set(&mut config.lsp.rust_analyzer_bin, self.rust_analyzer_bin);
+        set(&mut config.lsp.max_sessions, self.lsp_max_sessions);
+        set(
+            &mut config.lsp.max_sessions_per_client,
+            self.lsp_max_sessions_per_client,
+        );
+        set(
+            &mut config.lsp.trust_forwarded_for,
+            self.lsp_trust_forwarded_for,
+        );
+        set(
+            &mut config.lsp.idle_timeout_secs,
+            self.lsp_idle_timeout_secs,
+        );
+        set(&mut config.lsp.memory_limit_mb, self.lsp_memory_limit_mb);
+
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), ConfigError> {
+        fn invalid(key: &'static str, reason: impl Into<String>) -> ConfigError {
+            ConfigError::Invalid {
+                key,
+                reason: reason.into(),
+            }
+        }
+
+        if self.trunk_bin.is_empty() {
+            return Err(invalid("trunk_bin", "must not be empty"));
+        }
+        if self.wasm_opt_bin.is_empty() {
+            return Err(invalid("wasm_opt_bin", "must not be empty"));
+        }
+        if self.tailwind_bin.is_empty() {
+            return Err(invalid("tailwind_bin", "must not be empty"));
+        }
+        if self.rustfmt_bin.is_empty() {
+            return Err(invalid("rustfmt_bin", "must not be empty"));
+        }
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.log.filter) {
+            return Err(invalid("log.filter", e.to_string()));
+        }
+        if self.templates.stable.as_os_str().is_empty() {
+            return Err(invalid("templates.stable", "must not be empty"));
+        }
+        if self.templates.next.as_os_str().is_empty() {
+            return Err(invalid("templates.next", "must not be empty"));
+        }
+        if self.compile.timeout_secs == 0 {
+            return Err(invalid("compile.timeout_secs", "must be at least 1"));
+        }
+        if self.compile.concurrency == 0 {
+            return Err(invalid("compile.concurrency", "must be at least 1"));
+        }
+        if self.cors.origins.is_empty() {
+            return Err(invalid("cors.origins", "must list at least one origin"));
+        }
+        if self.cors.origins.len() > 1 && self.cors.origins.iter().any(|it| it == "*") {
+            return Err(invalid(
+                "cors.origins",
+                "`*` cannot be combined with other origins",
+            ));
+        }
+        if let Some(origin) = self
+            .cors
+            .origins
+            .iter()
+            .find(|it| HeaderValue::from_str(it).is_err())
+        {
+            return Err(invalid(
+                "cors.origins",
+                format!("`{origin}` is not a valid origin"),
+            ));
+        }
+        if self.cache.dir.as_os_str().is_empty() {
+            return Err(invalid("cache.dir", "must not be empty"));
+        }
+        match reqwest::Url::parse(&self.cache.storage_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => return Err(invalid("cache.storage_url", "must be an http(s) URL")),
+            Err(e) => return Err(invalid("cache.storage_url", e.to_string())),
+        }
+        if self.share.dir.as_os_str().is_empty() {
+            return Err(invalid("share.dir", "must not be empty"));
+        }
+        if self.share.max_size == 0 {
+            return Err(invalid("share.max_size", "must be at least 1"));
+        }
+        if matches!(self.share.store, ShareStoreKind::Firestore) {
+            match reqwest::Url::parse(&self.share.firestore_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => return Err(invalid("share.firestore_url", "must be an http(s) URL")),
+                Err(_) if self.share.firestore_url.is_empty() => {
+                    return Err(invalid(
+                        "share.firestore_url",
+                        "must be set for the `firestore` store",
+                    ));
+                }
+                Err(e) => return Err(invalid("share.firestore_url", e.to_string())),
+            }
+        }
+        if !self.share.legacy_pastes_url.is_empty() {
+            match reqwest::Url::parse(&self.share.legacy_pastes_url) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => {
+                    return Err(invalid(
+                        "share.legacy_pastes_url",
+                        "must be empty or an http(s) URL",
+                    ));
+                }
+                Err(e) => return Err(invalid("share.legacy_pastes_url", e.to_string())),
+            }
+        }
+        if self.share.firestore_collection.is_empty()
+            || self.share.firestore_collection.contains('/')
+        {
+            return Err(invalid(
+                "share.firestore_collection",
+                "must be a collection name without `/`",
+            ));
+        }
+        match reqwest::Url::parse(&self.import.github_api) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => return Err(invalid("import.github_api", "must be an http(s) URL")),
+            Err(e) => return Err(invalid("import.github_api", e.to_string())),
+        }
+        if self.import.max_size == 0 {
+            return Err(invalid("import.max_size", "must be at least 1"));
+        }
+        if self.import.timeout_secs == 0 {
+            return Err(invalid("import.timeout_secs", "must be at least 1"));
+        }
+        if self.lsp.rust_analyzer_bin.is_empty() {
+            return Err(invalid("lsp.rust_analyzer_bin", "must not be empty"));
+        }
+        if self.lsp.max_sessions == 0 {
+            return Err(invalid("lsp.max_sessions", "must be at least 1"));
+        }
+        if self.lsp.max_sessions_per_client == 0 {
+            return Err(invalid("lsp.max_sessions_per_client", "must be at least 1"));
+        }
+        if self.lsp.idle_timeout_secs == 0 {
+            return Err(invalid("lsp.idle_timeout_secs", "must be at least 1"));
+        }
+        if self.lsp.memory_limit_mb == 0 {
+            return Err(invalid("lsp.memory_limit_mb", "must be at least 1"));
+        }
+        Ok(())
+    }
+
+    pub fn compile_timeout(&self) -> Duration {
+        Duration::from_secs(self.compile.timeout_secs)
+    }
+
+    /// Whether a request from a page at `origin` is allowed, for endpoints that browsers do not
+    /// apply CORS to, such as websockets. Requests without an origin only pass when any origin
+    /// is allowed.
+    pub fn allows_origin(&self, origin: Option<&HeaderValue>) -> bool {
+        if self.cors.origins.iter().any(|it| it == "*") {
+            return true;
+        }
+        origin.is_some_and(|origin| self.cors.origins.iter().any(|it| origin == it.as_str()))
+    }
+
+    pub fn cors_layer(&self) -> CorsLayer {
+        if self.cors.origins.iter().any(|it| it == "*") {
+            return CorsLayer::permissive();
+        }
+        let origins = self
+            .cors
+            .origins
+            .iter()
+            .map(|it| HeaderValue::from_str(it).expect("origins are validated on load"));
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(origins))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+
+    use super::*;
+
+    /// Held by every test that reads the environment, which parsing the flags does, so the one
+    /// test that changes it never runs alongside them.
+    static ENV: Mutex<()> = Mutex::new(());
+
+    fn lock_env() -> MutexGuard<'static, ()> {
+        ENV.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Writes `toml` to a config file of its own and returns its path.
+    fn config_file(name: &str, toml: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "playground-config-test-{name}-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(&path, toml).unwrap();
+        path
+    }
+
+    /// Parses `args` with the environment as it is. Callers hold [`ENV`].
+    fn load(args: &[&str]) -> Result<Config, ConfigError> {
+        Cli::try_parse_from([&["playground-backend"], args].concat())
+            .unwrap()
+            .load()
+    }
+
+    #[test]
+    fn flags_override_env_overrides_file() {
+        let path = config_file(
+            "precedence",
+            "[compile]\nconcurrency = 2\nmax_queued = 3\n\n[cache]\ncapacity = 4\n",
+        );
+        let _env = lock_env();
+        // SAFETY: the tests that read the environment wait for `ENV`, which is held until the
+        // variables are removed again
+        unsafe {
+            std::env::set_var("PLAYGROUND_COMPILE_MAX_QUEUED", "30");
+            std::env::set_var("PLAYGROUND_CACHE_CAPACITY", "40");
+        }
+        let config = load(&[
+            "--config",
+            path.to_str().unwrap(),
+            "--cache-capacity",
+            "400",
+        ]);
+        unsafe {
+            std::env::remove_var("PLAYGROUND_COMPILE_MAX_QUEUED");
+            std::env::remove_var("PLAYGROUND_CACHE_CAPACITY");
+        }
+        let config = config.unwrap();
+        assert_eq!(config.compile.concurrency, 2);
+        assert_eq!(config.compile.max_queued, 30);
+        assert_eq!(config.cache.capacity, 400);
+        // untouched keys keep their defaults
+        assert_eq!(
+            config.compile.timeout_secs,
+            CompileConfig::default().timeout_secs
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let _env = lock_env();
+        for (name, toml) in [
+            ("top-level", "prot = 8080\n"),
+            ("nested", "[compile]\ntimeout = 60\n"),
+        ] {
+            let path = config_file(name, toml);
+            let result = load(&["--config", path.to_str().unwrap()]);
+            assert!(
+                matches!(result, Err(ConfigError::Parse { .. })),
+                "{name}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_values_that_do_not_parse() {
+        let _env = lock_env();
+        // the environment goes through the same parser, `PORT=abc` fails just the same
+        assert!(Cli::try_parse_from(["playground-backend", "--port", "abc"]).is_err());
+    }
+
+    #[test]
+    fn validates_values() {
+        assert!(Config::default().validate().is_ok());
+
+        type Break = fn(&mut Config);
+        let cases: &[(&str, Break)] = &[
+            ("trunk_bin", |c| c.trunk_bin.clear()),
+            ("wasm_opt_bin", |c| c.wasm_opt_bin.clear()),
+            ("tailwind_bin", |c| c.tailwind_bin.clear()),
+            ("rustfmt_bin", |c| c.rustfmt_bin.clear()),
+            ("log.filter", |c| c.log.filter = "info,=[".to_string()),
+            ("templates.stable", |c| c.templates.stable = PathBuf::new()),
+            ("templates.next", |c| c.templates.next = PathBuf::new()),
+            ("compile.timeout_secs", |c| c.compile.timeout_secs = 0),
+            ("compile.concurrency", |c| c.compile.concurrency = 0),
+            ("cors.origins", |c| c.cors.origins.clear()),
+            ("cors.origins", |c| {
+                c.cors.origins = vec!["*".to_string(), "https://example.com".to_string()]
+            }),
+            ("cors.origins", |c| {
+                c.cors.origins = vec!["a\nb".to_string()]
+            }),
+            ("cache.dir", |c| c.cache.dir = PathBuf::new()),
+            ("cache.storage_url", |c| {
+                c.cache.storage_url = "gs://artifacts".to_string()
+            }),
+            ("cache.storage_url", |c| {
+                c.cache.storage_url = "not a url".to_string()
+            }),
+            ("share.dir", |c| c.share.dir = PathBuf::new()),
+            ("share.max_size", |c| c.share.max_size = 0),
+            ("share.firestore_url", |c| {
+                c.share.store = ShareStoreKind::Firestore
+            }),
+            ("share.firestore_url", |c| {
+                c.share.store = ShareStoreKind::Firestore;
+                c.share.firestore_url = "ftp://firestore".to_string()
+            }),
+            ("share.firestore_url", |c| {
+                c.share.store = ShareStoreKind::Firestore;
+                c.share.firestore_url = "not a url".to_string()
+            }),
+            ("share.legacy_pastes_url", |c| {
+                c.share.legacy_pastes_url = "ftp://firestore/pastes".to_string()
+            }),
+            ("share.legacy_pastes_url", |c| {
+                c.share.legacy_pastes_url = "not a url".to_string()
+            }),
+            ("share.firestore_collection", |c| {
+                c.share.firestore_collection.clear()
+            }),
+            ("share.firestore_collection", |c| {
+                c.share.firestore_collection = "shares/nested".to_string()
+            }),
+            ("import.github_api", |c| {
+                c.import.github_api = "ftp://github".to_string()
+            }),
+            ("import.github_api", |c| {
+                c.import.github_api = "not a url".to_string()
+            }),
+            ("import.max_size", |c| c.import.max_size = 0),
+            ("import.timeout_secs", |c| c.import.timeout_secs = 0),
+            ("lsp.rust_analyzer_bin", |c| c.lsp.rust_analyzer_bin.clear()),
+            ("lsp.max_sessions", |c| c.lsp.max_sessions = 0),
+            ("lsp.max_sessions_per_client", |c| {
+                c.lsp.max_sessions_per_client = 0
+            }),
+            ("lsp.idle_timeout_secs", |c| c.lsp.idle_timeout_secs = 0),
+            ("lsp.memory_limit_mb", |c| c.lsp.memory_limit_mb = 0),
+        ];
+        for (expected, break_config) in cases {
+            let mut config = Config::default();
+            break_config(&mut config);
+            match config.validate() {
+                Err(ConfigError::Invalid { key, .. }) => assert_eq!(key, *expected),
+                other => panic!("{expected}: expected it to be rejected, got {other:?}"),
+            }
+        }
+        // the Firestore URL only matters to the `firestore` store
+        let mut config = Config::default();
+        config.share.firestore_url = "not a url".to_string();
+        assert!(config.validate().is_ok());
+        // an empty legacy URL turns the pastes off rather than being invalid
+        let mut config = Config::default();
+        config.share.legacy_pastes_url.clear();
+        assert!(config.validate().is_ok());
+    }
+}
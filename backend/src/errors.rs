@@ -1,37 +1,111 @@
-use axum::http::StatusCode;
+use axum::Json;
+use axum::http::{HeaderValue, StatusCode, header};
 use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// Machine-readable error category, sent as `kind` in every error response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    CompileFailed,
+    Timeout,
+    QueueFull,
+    InvalidInput,
+    NotFound,
+    TooLarge,
+    Forbidden,
+    RateLimited,
+    Internal,
+}
+
+/// The JSON body of every error response returned by the API.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub kind: ErrorKind,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum ApiError {
+    #[error("compilation failed")]
+    CompileFailed { html: String },
+    #[error("request took too long")]
+    Timeout,
+    #[error("too many compilations are queued, try again later")]
+    QueueFull,
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    TooLarge(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    RateLimited(String),
     #[error("{0}")]
     IoError(std::io::Error),
-    #[error("request must have a body but none was found")]
-    NoBody,
     #[error(transparent)]
     Unknown(#[from] anyhow::Error),
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let status = match self {
-            ApiError::IoError(_) => StatusCode::INTERNAL_SERVER_ERROR,
-            ApiError::NoBody => StatusCode::BAD_REQUEST,
-            ApiError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
-        (status, self.to_string()).into_response()
+/// Seconds a client should wait before retrying when the queue is full.
+const QUEUE_FULL_RETRY_AFTER: HeaderValue = HeaderValue::from_static("5");
+
+impl ApiError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            ApiError::CompileFailed { .. } => ErrorKind::CompileFailed,
+            ApiError::Timeout => ErrorKind::Timeout,
+            ApiError::QueueFull => ErrorKind::QueueFull,
+            ApiError::InvalidInput(_) => ErrorKind::InvalidInput,
+            ApiError::NotFound(_) => ErrorKind::NotFound,
+            ApiError::TooLarge(_) => ErrorKind::TooLarge,
+            ApiError::Forbidden(_) => ErrorKind::Forbidden,
+            ApiError::RateLimited(_) => ErrorKind::RateLimited,
+            ApiError::IoError(_) | ApiError::Unknown(_) => ErrorKind::Internal,
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self.kind() {
+            ErrorKind::CompileFailed => StatusCode::UNPROCESSABLE_ENTITY,
+            ErrorKind::Timeout => StatusCode::REQUEST_TIMEOUT,
+            ErrorKind::QueueFull => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::InvalidInput => StatusCode::BAD_REQUEST,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
+            ErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            ApiError::CompileFailed { html } => Some(json!({ "html": html })),
+            _ => None,
+        }
     }
 }
 
-pub async fn timeout_or_500(err: axum::BoxError) -> (StatusCode, String) {
-    if err.is::<tower::timeout::error::Elapsed>() {
-        (
-            StatusCode::REQUEST_TIMEOUT,
-            "Request took too long".to_string(),
-        )
-    } else {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Unhandled internal error: {}", err),
-        )
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        let body = ErrorBody {
+            kind: self.kind(),
+            message: self.to_string(),
+            details: self.details(),
+        };
+        let mut response = (status, Json(body)).into_response();
+        if let ApiError::QueueFull = self {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, QUEUE_FULL_RETRY_AFTER);
+        }
+        response
     }
 }
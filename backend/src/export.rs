@@ -0,0 +1,397 @@
+//! Downloads of a playground project as a Cargo project that builds with trunk, so users can
+//! continue locally.
+
+use std::io::{self, Cursor, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use axum::Json;
+use axum::extract::rejection::JsonRejection;
+use axum::http::header;
+use axum::response::IntoResponse;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use tokio::fs;
+use tracing::error;
+use zip::write::SimpleFileOptions;
+
+use crate::compile::YewVersion;
+use crate::errors::ApiError;
+use crate::page::{DEFAULT_TITLE, Page};
+
+/// Input stylesheet of exports with Tailwind, built by trunk's `tailwind-css` asset. Like the
+/// playground's own input, it only scans the code for class names.
+const TAILWIND_CSS: &str = r#"@import "tailwindcss" source(none);
+@source "./src";
+"#;
+/// The Tailwind CLI trunk fetches for exports, the version the backend image installs.
+const TAILWIND_VERSION: &str = "4.1.13";
+
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "tar.gz")]
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "application/gzip",
+            ArchiveFormat::Zip => "application/zip",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ExportPayload {
+    code: String,
+    #[serde(default)]
+    version: YewVersion,
+    /// Names the crate and the archive.
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    format: ArchiveFormat,
+    /// Build Tailwind CSS for the classes used in `code`, as runs with `tailwind` do.
+    #[serde(default)]
+    tailwind: bool,
+    #[serde(flatten)]
+    page: Page,
+}
+
+/// The files of a template that go into an export as they are, or nearly.
+struct Template {
+    cargo_toml: String,
+    cargo_lock: Option<Vec<u8>>,
+    trunk_toml: Vec<u8>,
+    index_html: String,
+}
+
+impl Template {
+    async fn read(dir: &Path) -> Result<Self, ApiError> {
+        let io_error = |e| {
+            error!(?e, ?dir, "failed to read template");
+            ApiError::IoError(e)
+        };
+        let cargo_lock = match fs::read(dir.join("Cargo.lock")).await {
+            Ok(lock) => Some(lock),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(io_error(e)),
+        };
+        Ok(Template {
+            cargo_toml: fs::read_to_string(dir.join("Cargo.toml"))
+                .await
+                .map_err(io_error)?,
+            cargo_lock,
+            trunk_toml: fs::read(dir.join("Trunk.toml")).await.map_err(io_error)?,
+            index_html: fs::read_to_string(dir.join("index.html"))
+                .await
+                .map_err(io_error)?,
+        })
+    }
+}
+
+/// Turns `title` into a crate name, e.g. `My Counter!` into `my-counter`.
+fn crate_name(title: &str) -> String {
+    let mut name = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.truncate(64);
+    let name = name.trim_end_matches('-');
+    match name.chars().next() {
+        None => "yew-app".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("app-{name}"),
+        Some(_) => name.to_string(),
+    }
+}
+
+/// Renames the template's package. The manifest is edited as text so its formatting and
+/// comments survive.
+fn rename_package(cargo_toml: &str, name: &str) -> String {
+    let mut renamed = false;
+    cargo_toml
+        .lines()
+        .map(|line| {
+            if !renamed && line.trim_start().starts_with("name =") {
+                renamed = true;
+                format!("name = \"{name}\"")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
+
+/// Adds the user's markup to the template's `index.html`, laid out like [`Page::render`]: the
+/// user's head comes before the default title, so a title given there wins, and the stylesheets
+/// follow. They are linked as trunk assets rather than inlined, so they can be edited as files.
+fn index_html(template: &str, page: &Page, tailwind: bool) -> String {
+    let mut html = template.to_string();
+    let mut head = String::new();
+    if !page.head.is_empty() {
+        head.push_str(&page.head);
+        head.push_str("\n    ");
+    }
+    let title = format!("<title>{DEFAULT_TITLE}</title>");
+    match (html.find("<title>"), html.find("</title>")) {
+        (Some(start), Some(end)) if start < end => {
+            html.replace_range(start..end + "</title>".len(), &format!("{head}{title}"));
+        }
+        _ => {
+            if let Some(at) = html.find("</head>") {
+                html.insert_str(at, &format!("    {head}{title}\n"));
+            }
+        }
+    }
+
+    let mut styles = String::new();
+    // the generated stylesheet comes first so the user's can override it, as in runs
+    if tailwind {
+        styles.push_str("    <link data-trunk rel=\"tailwind-css\" href=\"tailwind.css\" />\n");
+    }
+    if !page.css.is_empty() {
+        styles.push_str("    <link data-trunk rel=\"css\" href=\"style.css\" />\n");
+    }
+    if let Some(at) = html.find("</head>") {
+        html.insert_str(at, &styles);
+    }
+    match html.find("<body>") {
+        Some(at) => {
+            let at = at + "<body>".len();
+            format!("{}{}{}", &html[..at], page.body, &html[at..])
+        }
+        None => html,
+    }
+}
+
+/// Paths and contents of the exported project.
+fn project_files(
+    template: Template,
+    name: &str,
+    code: String,
+    page: &Page,
+    tailwind: bool,
+) -> Vec<(String, Vec<u8>)> {
+    let mut trunk_toml = template.trunk_toml;
+    if tailwind {
+        trunk_toml.extend(format!("\n[tools]\ntailwindcss = \"{TAILWIND_VERSION}\"\n").bytes());
+    }
+    let mut files = vec![
+        (
+            "Cargo.toml".to_string(),
+            rename_package(&template.cargo_toml, name).into_bytes(),
+        ),
+        ("Trunk.toml".to_string(), trunk_toml),
+        (
+            "index.html".to_string(),
+            index_html(&template.index_html, page, tailwind).into_bytes(),
+        ),
+        ("src/main.rs".to_string(), code.into_bytes()),
+        (".gitignore".to_string(), b"/target\n/dist\n".to_vec()),
+    ];
+    // pins the dependencies to the versions the playground runs
+    if let Some(lock) = template.cargo_lock {
+        let lock = String::from_utf8_lossy(&lock).replacen(
+            "name = \"app\"",
+            &format!("name = \"{name}\""),
+            1,
+        );
+        files.push(("Cargo.lock".to_string(), lock.into_bytes()));
+    }
+    if !page.css.is_empty() {
+        files.push(("style.css".to_string(), page.css.clone().into_bytes()));
+    }
+    if tailwind {
+        files.push(("tailwind.css".to_string(), TAILWIND_CSS.as_bytes().to_vec()));
+    }
+    files
+}
+
+fn tar_gz(root: &str, files: &[(String, Vec<u8>)]) -> io::Result<Vec<u8>> {
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|it| it.as_secs())
+        .unwrap_or_default();
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        builder.append_data(&mut header, format!("{root}/{path}"), data.as_slice())?;
+    }
+    builder.into_inner()?.finish()
+}
+
+fn zip_archive(root: &str, files: &[(String, Vec<u8>)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .unix_permissions(0o644);
+    for (path, data) in files {
+        writer.start_file(format!("{root}/{path}"), options)?;
+        writer.write_all(data)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+pub async fn export(
+    json: Result<Json<ExportPayload>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(payload) = json.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    if payload.code.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "code must be provided but none was found".to_string(),
+        ));
+    }
+
+    let template = Template::read(payload.version.app_dir()).await?;
+    let name = crate_name(&payload.title);
+    let format = payload.format;
+    let tailwind = payload.tailwind;
+    let archive = tokio::task::spawn_blocking({
+        let name = name.clone();
+        move || {
+            let files = project_files(template, &name, payload.code, &payload.page, tailwind);
+            match format {
+                ArchiveFormat::TarGz => tar_gz(&name, &files).map_err(anyhow::Error::from),
+                ArchiveFormat::Zip => zip_archive(&name, &files).map_err(anyhow::Error::from),
+            }
+        }
+    })
+    .await
+    .map_err(anyhow::Error::from)?
+    .map_err(|e| anyhow!("failed to build archive: {e}"))?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{name}.{}\"", format.extension()),
+            ),
+        ],
+        archive,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::GzDecoder;
+
+    use super::*;
+
+    #[test]
+    fn names_crates_after_titles() {
+        assert_eq!(crate_name("My Counter!"), "my-counter");
+        assert_eq!(crate_name("  --  "), "yew-app");
+        assert_eq!(crate_name("2048"), "app-2048");
+    }
+
+    #[test]
+    fn builds_a_trunk_project() {
+        let template = Template {
+            cargo_toml: "[package]\nname = \"app\"\n\n[dependencies]\nyew = \"0.23\"\n".to_string(),
+            cargo_lock: None,
+            trunk_toml: b"[build]\n".to_vec(),
+            index_html:
+                "<html>\n<head>\n    <title>Title</title>\n</head>\n<body></body>\n</html>\n"
+                    .to_string(),
+        };
+        let page = Page {
+            head: "<meta name=\"x\">\n    <title>Counter</title>".to_string(),
+            body: "<div id=\"root\"></div>".to_string(),
+            css: "body {}".to_string(),
+        };
+        let files = project_files(
+            template,
+            "counter",
+            "fn main() {}".to_string(),
+            &page,
+            false,
+        );
+        let archive = tar_gz("counter", &files).unwrap();
+
+        let mut entries = Vec::new();
+        let mut tar = tar::Archive::new(GzDecoder::new(archive.as_slice()));
+        for entry in tar.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            entries.push((entry.path().unwrap().display().to_string(), data));
+        }
+        let file = |path: &str| {
+            entries
+                .iter()
+                .find(|(it, _)| it == path)
+                .map(|(_, data)| data.as_str())
+                .unwrap()
+        };
+        assert!(file("counter/Cargo.toml").contains("name = \"counter\""));
+        assert_eq!(file("counter/src/main.rs"), "fn main() {}");
+        assert_eq!(file("counter/style.css"), "body {}");
+        let index = file("counter/index.html");
+        assert!(index.contains("<meta name=\"x\">"));
+        assert!(index.contains("href=\"style.css\""));
+        assert!(index.contains("<body><div id=\"root\"></div></body>"));
+        assert!(!index.contains("tailwind"));
+        // the user's title comes first and wins over the default, as in runs
+        let title = |it: &str| index.find(it).unwrap();
+        assert!(
+            title("<title>Counter</title>") < title(&format!("<title>{DEFAULT_TITLE}</title>"))
+        );
+        assert!(!index.contains("<title>Title</title>"));
+    }
+
+    #[test]
+    fn builds_tailwind_with_trunk() {
+        let template = Template {
+            cargo_toml: "[package]\nname = \"app\"\n".to_string(),
+            cargo_lock: None,
+            trunk_toml: b"[build]\n".to_vec(),
+            index_html: "<html>\n<head>\n</head>\n<body></body>\n</html>\n".to_string(),
+        };
+        let files = project_files(
+            template,
+            "styled",
+            "fn main() {}".to_string(),
+            &Page::default(),
+            true,
+        );
+        let file = |path: &str| {
+            files
+                .iter()
+                .find(|(it, _)| it == path)
+                .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+                .unwrap()
+        };
+        assert_eq!(file("tailwind.css"), TAILWIND_CSS);
+        assert!(file("index.html").contains("rel=\"tailwind-css\" href=\"tailwind.css\""));
+        let trunk_toml: toml::Table = toml::from_str(&file("Trunk.toml")).unwrap();
+        assert_eq!(
+            trunk_toml["tools"]["tailwindcss"].as_str(),
+            Some(TAILWIND_VERSION)
+        );
+    }
+}
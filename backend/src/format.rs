@@ -0,0 +1,88 @@
+//! Formatting code with rustfmt, for the editor's format action.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::rejection::JsonRejection;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tracing::{debug, error};
+
+use crate::config::config;
+use crate::errors::ApiError;
+
+/// rustfmt takes well under a second, anything longer is stuck.
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize)]
+pub struct FormatPayload {
+    code: String,
+}
+
+#[derive(Serialize)]
+pub struct Formatted {
+    code: String,
+}
+
+/// `POST /format`, returning the code formatted the way the templates' edition does.
+pub async fn format(
+    json: Result<Json<FormatPayload>, JsonRejection>,
+) -> Result<Json<Formatted>, ApiError> {
+    let Json(payload) = json.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    let code = tokio::time::timeout(
+        FORMAT_TIMEOUT,
+        rustfmt(&config().rustfmt_bin, &payload.code),
+    )
+    .await
+    .unwrap_or(Err(ApiError::Timeout))?;
+    Ok(Json(Formatted { code }))
+}
+
+async fn rustfmt(bin: &str, code: &str) -> Result<String, ApiError> {
+    let io_error = |e| {
+        error!(?e, "running rustfmt failed");
+        ApiError::IoError(e)
+    };
+    let mut cmd = Command::new(bin);
+    let cmd = cmd
+        .args(["--edition", "2024"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    debug!(?cmd, "running command");
+
+    let mut child = cmd.spawn().map_err(io_error)?;
+    // rustfmt reads all of its input before writing, so this cannot block on a full stdout
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    stdin.write_all(code.as_bytes()).await.map_err(io_error)?;
+    drop(stdin);
+
+    let output = child.wait_with_output().await.map_err(io_error)?;
+    if !output.status.success() {
+        // rustfmt fails on code that does not parse, which is the user's to fix
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ApiError::InvalidInput(stderr.trim().to_string()));
+    }
+    String::from_utf8(output.stdout)
+        .map_err(|e| ApiError::Unknown(anyhow::anyhow!("rustfmt output is not UTF-8: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn formats_code() {
+        assert_eq!(
+            rustfmt("rustfmt", "fn main(){let x=1;}").await.unwrap(),
+            "fn main() {\n    let x = 1;\n}\n"
+        );
+        match rustfmt("rustfmt", "fn main({").await {
+            Err(ApiError::InvalidInput(message)) => assert!(message.contains("unclosed delimiter")),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+}
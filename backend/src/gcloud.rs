@@ -0,0 +1,67 @@
+//! Access to Google Cloud APIs as the service account the backend runs as.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use serde::Deserialize;
+
+/// Where Cloud Run and other Google Cloud runtimes hand out their service account's tokens.
+const TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+/// Tokens are renewed this long before they expire.
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// The service account's access token, fetched from the metadata server and reused until
+/// shortly before it expires.
+#[derive(Default)]
+pub struct AccessToken {
+    cached: Mutex<Option<Token>>,
+}
+
+struct Token {
+    value: String,
+    expires_at: Instant,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl AccessToken {
+    pub async fn get(&self, client: &reqwest::Client) -> anyhow::Result<String> {
+        if let Some(token) = &*self.cached.lock().unwrap()
+            && token.expires_at > Instant::now() + TOKEN_MARGIN
+        {
+            return Ok(token.value.clone());
+        }
+        let resp = client
+            .get(TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await
+            .context("failed to get a token from the metadata server")?
+            .error_for_status()?;
+        let token: TokenResponse = serde_json::from_slice(&resp.bytes().await?)?;
+        *self.cached.lock().unwrap() = Some(Token {
+            value: token.access_token.clone(),
+            expires_at: Instant::now() + Duration::from_secs(token.expires_in),
+        });
+        Ok(token.access_token)
+    }
+}
+
+/// Adds the token to `request` if there is one, which there is not for emulators and
+/// stand-ins.
+pub async fn authorize(
+    request: reqwest::RequestBuilder,
+    client: &reqwest::Client,
+    token: Option<&AccessToken>,
+) -> anyhow::Result<reqwest::RequestBuilder> {
+    Ok(match token {
+        Some(token) => request.bearer_auth(token.get(client).await?),
+        None => request,
+    })
+}
@@ -0,0 +1,376 @@
+//! Inspection of the JS glue wasm-bindgen generates for `--target web`, which is what trunk
+//! emits as `app.js`.
+//!
+//! The page imports the glue as an ES module and calls its default export, so the only things
+//! we need to know are that a default export exists and which arguments it accepts. Both are
+//! read from the module's tokens instead of its exact text, so formatting changes between
+//! wasm-bindgen releases do not break us.
+
+/// The argument the init function (the glue's default export) expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitConvention {
+    /// `init({ module_or_path })`, wasm-bindgen 0.2.93 and later.
+    Options,
+    /// `init(module_or_path)`, earlier versions.
+    Positional,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glue {
+    /// Local name of the default export.
+    pub init: String,
+    pub convention: InitConvention,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GlueError {
+    #[error("app.js has no default export, it was probably not generated with `--target web`")]
+    NoDefaultExport,
+    #[error("app.js has an unterminated {0}")]
+    Unterminated(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Punct(char),
+    /// String, template, regex and number literals. Their contents never matter here.
+    Literal,
+}
+
+/// Splits JS source into tokens, dropping comments and literal contents.
+///
+/// This is not a full JS lexer. It knows just enough (comments, strings, template literals
+/// with substitutions, regex literals) to never mistake text inside them for code.
+fn tokenize(src: &str) -> Result<Vec<Token<'_>>, GlueError> {
+    let bytes = src.as_bytes();
+    let mut tokens = Vec::new();
+    // Brace depth of each open template substitution (`${`), innermost last
+    let mut templates: Vec<usize> = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match c {
+            b if b.is_ascii_whitespace() => i += 1,
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = src[i..].find('\n').map_or(bytes.len(), |it| i + it);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                let end = src[i + 2..]
+                    .find("*/")
+                    .ok_or(GlueError::Unterminated("comment"))?;
+                i += 2 + end + 2;
+            }
+            b'/' if regex_allowed(tokens.last()) => {
+                i = skip_regex(bytes, i)?;
+                tokens.push(Token::Literal);
+            }
+            b'\'' | b'"' => {
+                i = skip_string(bytes, i)?;
+                tokens.push(Token::Literal);
+            }
+            b'`' => {
+                i = skip_template(bytes, i + 1, &mut templates, depth)?;
+                tokens.push(Token::Literal);
+            }
+            b'}' if templates.last() == Some(&depth) => {
+                // end of a `${...}` substitution, the template literal continues
+                templates.pop();
+                i = skip_template(bytes, i + 1, &mut templates, depth)?;
+            }
+            b if b.is_ascii_digit() => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                tokens.push(Token::Literal);
+            }
+            b if is_ident_byte(b) => {
+                let start = i;
+                while i < bytes.len() && is_ident_byte(bytes[i]) {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(&src[start..i]));
+            }
+            _ => {
+                match c {
+                    b'{' => depth += 1,
+                    b'}' => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                let ch = src[i..]
+                    .chars()
+                    .next()
+                    .expect("index is on a char boundary");
+                tokens.push(Token::Punct(ch));
+                i += ch.len_utf8();
+            }
+        }
+    }
+
+    if templates.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(GlueError::Unterminated("template literal"))
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || !b.is_ascii()
+}
+
+/// Whether a `/` after `prev` starts a regex literal rather than being a division.
+fn regex_allowed(prev: Option<&Token>) -> bool {
+    match prev {
+        None => true,
+        Some(Token::Punct(c)) => !matches!(c, ')' | ']' | '}'),
+        Some(Token::Ident(word)) => matches!(
+            *word,
+            "return"
+                | "typeof"
+                | "instanceof"
+                | "in"
+                | "of"
+                | "new"
+                | "delete"
+                | "void"
+                | "throw"
+                | "case"
+                | "do"
+                | "else"
+                | "yield"
+                | "await"
+        ),
+        Some(Token::Literal) => false,
+    }
+}
+
+fn skip_string(bytes: &[u8], start: usize) -> Result<usize, GlueError> {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b if b == quote => return Ok(i + 1),
+            b'\n' => break,
+            _ => i += 1,
+        }
+    }
+    Err(GlueError::Unterminated("string"))
+}
+
+fn skip_regex(bytes: &[u8], start: usize) -> Result<usize, GlueError> {
+    let mut i = start + 1;
+    let mut in_class = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'[' => {
+                in_class = true;
+                i += 1;
+            }
+            b']' => {
+                in_class = false;
+                i += 1;
+            }
+            b'/' if !in_class => {
+                i += 1;
+                while i < bytes.len() && bytes[i].is_ascii_alphabetic() {
+                    i += 1;
+                }
+                return Ok(i);
+            }
+            b'\n' => break,
+            _ => i += 1,
+        }
+    }
+    Err(GlueError::Unterminated("regex"))
+}
+
+/// Skips template literal text starting after a backtick or a substitution's closing brace.
+/// Stops after the closing backtick, or after `${` with the substitution recorded in
+/// `templates` so the caller tokenizes its contents.
+fn skip_template(
+    bytes: &[u8],
+    mut i: usize,
+    templates: &mut Vec<usize>,
+    depth: usize,
+) -> Result<usize, GlueError> {
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'`' => return Ok(i + 1),
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                templates.push(depth);
+                return Ok(i + 2);
+            }
+            _ => i += 1,
+        }
+    }
+    Err(GlueError::Unterminated("template literal"))
+}
+
+/// Finds the local name of the default export in `export default x;`,
+/// `export default [async] function x(` and `export { x as default }`.
+fn default_export<'a>(tokens: &[Token<'a>]) -> Option<&'a str> {
+    tokens.iter().enumerate().find_map(|(i, token)| {
+        if *token != Token::Ident("export") {
+            return None;
+        }
+        match tokens.get(i + 1)? {
+            Token::Ident("default") => tokens[i + 2..]
+                .iter()
+                .find_map(|it| match it {
+                    Token::Ident("async" | "function") => None,
+                    Token::Ident(name) => Some(Some(*name)),
+                    _ => Some(None),
+                })
+                .flatten(),
+            Token::Punct('{') => {
+                let list = &tokens[i + 2..];
+                let end = list.iter().position(|it| *it == Token::Punct('}'))?;
+                list[..end].windows(3).find_map(|it| match it {
+                    [
+                        Token::Ident(name),
+                        Token::Ident("as"),
+                        Token::Ident("default"),
+                    ] => Some(*name),
+                    _ => None,
+                })
+            }
+            _ => None,
+        }
+    })
+}
+
+/// Finds the name of the first parameter of `function name(`.
+fn first_param<'a>(tokens: &[Token<'a>], name: &str) -> Option<&'a str> {
+    tokens.windows(4).find_map(|it| match it {
+        [
+            Token::Ident("function"),
+            Token::Ident(fn_name),
+            Token::Punct('('),
+            Token::Ident(param),
+        ] if *fn_name == name => Some(*param),
+        _ => None,
+    })
+}
+
+pub fn inspect(js: &str) -> Result<Glue, GlueError> {
+    let tokens = tokenize(js)?;
+    let init = default_export(&tokens).ok_or(GlueError::NoDefaultExport)?;
+    // Newer versions name the parameter `module_or_path` and destructure an options object
+    // from it, older ones called it `input` and used it directly
+    let convention = match first_param(&tokens, init) {
+        Some("module_or_path") => InitConvention::Options,
+        _ => InitConvention::Positional,
+    };
+    Ok(Glue {
+        init: init.to_string(),
+        convention,
+    })
+}
+
+impl Glue {
+    /// The module script that loads the glue from `js_url` and instantiates `wasm_url`.
+    pub fn bootstrap(&self, js_url: &str, wasm_url: &str) -> String {
+        let args = match self.convention {
+            InitConvention::Options => format!("{{ module_or_path: {wasm_url:?} }}"),
+            InitConvention::Positional => format!("{wasm_url:?}"),
+        };
+        format!("import init from {js_url:?};\nawait init({args});")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_str!(concat!("../tests/fixtures/glue/", $name))
+        };
+    }
+
+    #[test]
+    fn web_target_before_options() {
+        let glue = inspect(fixture!("web-0.2.84.js")).unwrap();
+        assert_eq!(glue.init, "__wbg_init");
+        assert_eq!(glue.convention, InitConvention::Positional);
+    }
+
+    #[test]
+    fn web_target_with_options() {
+        let glue = inspect(fixture!("web-0.2.93.js")).unwrap();
+        assert_eq!(glue.init, "__wbg_init");
+        assert_eq!(glue.convention, InitConvention::Options);
+    }
+
+    #[test]
+    fn web_target_default_in_export_list() {
+        let glue = inspect(fixture!("web-0.2.100.js")).unwrap();
+        assert_eq!(glue.init, "__wbg_init");
+        assert_eq!(glue.convention, InitConvention::Options);
+    }
+
+    #[test]
+    fn web_target_legacy_init() {
+        let glue = inspect(fixture!("web-legacy.js")).unwrap();
+        assert_eq!(glue.init, "init");
+        assert_eq!(glue.convention, InitConvention::Positional);
+    }
+
+    #[test]
+    fn no_modules_target_is_rejected() {
+        assert_eq!(
+            inspect(fixture!("no-modules.js")),
+            Err(GlueError::NoDefaultExport)
+        );
+    }
+
+    #[test]
+    fn exports_in_comments_and_literals_are_ignored() {
+        let js = r#"
+            // export default commented;
+            /* export { block as default }; */
+            const s = "export default string;";
+            const t = `export default ${"nested"} template; ${ { a: 1 }.a }`;
+            const r = /export default regex;[/"']/g;
+            async function real(module_or_path) {}
+            export { real as default };
+        "#;
+        let glue = inspect(js).unwrap();
+        assert_eq!(glue.init, "real");
+        assert_eq!(glue.convention, InitConvention::Options);
+    }
+
+    #[test]
+    fn unterminated_template_is_an_error() {
+        assert_eq!(
+            inspect("const a = `oops ${b}"),
+            Err(GlueError::Unterminated("template literal"))
+        );
+    }
+
+    #[test]
+    fn bootstrap_matches_convention() {
+        let glue = Glue {
+            init: "__wbg_init".to_string(),
+            convention: InitConvention::Options,
+        };
+        assert_eq!(
+            glue.bootstrap("./app.js", "./app_bg.wasm"),
+            "import init from \"./app.js\";\nawait init({ module_or_path: \"./app_bg.wasm\" });"
+        );
+
+        let glue = Glue {
+            convention: InitConvention::Positional,
+            ..glue
+        };
+        assert_eq!(
+            glue.bootstrap("./app.js", "./app_bg.wasm"),
+            "import init from \"./app.js\";\nawait init(\"./app_bg.wasm\");"
+        );
+    }
+}
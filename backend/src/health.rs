@@ -0,0 +1,183 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use tokio::fs;
+use tokio::process::Command;
+use tracing::{error, info};
+
+use crate::compile::{self, OptLevel, YewVersion};
+use crate::config::config;
+use crate::queue::CompileQueue;
+
+/// The snippet compiled by the warm-up, relative to the template directory.
+const DEFAULT_SNIPPET: &str = "snippets/hello_world.rs";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WarmupState {
+    Disabled,
+    Running,
+    Done,
+    Failed,
+}
+
+pub struct Health {
+    warmup: AtomicU8,
+}
+
+impl Health {
+    pub fn new(warmup: bool) -> Self {
+        let state = if warmup {
+            WarmupState::Running
+        } else {
+            WarmupState::Disabled
+        };
+        Self {
+            warmup: AtomicU8::new(state as u8),
+        }
+    }
+
+    fn warmup_state(&self) -> WarmupState {
+        match self.warmup.load(Ordering::SeqCst) {
+            0 => WarmupState::Disabled,
+            1 => WarmupState::Running,
+            2 => WarmupState::Done,
+            _ => WarmupState::Failed,
+        }
+    }
+
+    fn set_warmup_state(&self, state: WarmupState) {
+        self.warmup.store(state as u8, Ordering::SeqCst);
+    }
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: String,
+    ok: bool,
+    detail: String,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    ready: bool,
+    checks: Vec<Check>,
+}
+
+pub fn router(health: Arc<Health>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(health)
+}
+
+/// Liveness: the process is up and serving requests.
+async fn healthz() -> &'static str {
+    "ok"
+}
+
+/// Readiness: compilations can be served right away.
+async fn readyz(State(health): State<Arc<Health>>) -> (StatusCode, Json<Readiness>) {
+    let mut checks = vec![check_trunk().await];
+    for version in YewVersion::ALL {
+        checks.extend(check_template(version).await);
+    }
+    checks.push(check_warmup(health.warmup_state()));
+
+    let ready = checks.iter().all(|it| it.ok);
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(Readiness { ready, checks }))
+}
+
+async fn check_trunk() -> Check {
+    let trunk_bin = &config().trunk_bin;
+    let (ok, detail) = match Command::new(trunk_bin).arg("--version").output().await {
+        Ok(output) if output.status.success() => (
+            true,
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        Ok(output) => (
+            false,
+            format!("`trunk --version` exited with {}", output.status),
+        ),
+        Err(e) => (false, format!("failed to run {trunk_bin}: {e}")),
+    };
+    Check {
+        name: "trunk".to_string(),
+        ok,
+        detail,
+    }
+}
+
+async fn check_template(version: YewVersion) -> [Check; 2] {
+    let app_dir = version.app_dir();
+    let dir_exists = fs::metadata(app_dir).await.is_ok_and(|it| it.is_dir());
+    let target = app_dir.join("target");
+    let target_exists = fs::metadata(&target).await.is_ok_and(|it| it.is_dir());
+    [
+        Check {
+            name: format!("template:{}", version.name()),
+            ok: dir_exists,
+            detail: app_dir.display().to_string(),
+        },
+        Check {
+            name: format!("target:{}", version.name()),
+            ok: target_exists,
+            detail: target.display().to_string(),
+        },
+    ]
+}
+
+fn check_warmup(state: WarmupState) -> Check {
+    let (ok, detail) = match state {
+        WarmupState::Disabled => (true, "disabled"),
+        WarmupState::Running => (false, "running"),
+        WarmupState::Done => (true, "done"),
+        WarmupState::Failed => (false, "failed"),
+    };
+    Check {
+        name: "warmup".to_string(),
+        ok,
+        detail: detail.to_string(),
+    }
+}
+
+/// Compiles each template's default snippet so the first real request hits warm caches.
+pub async fn warm_up(health: Arc<Health>, queue: Arc<CompileQueue>) {
+    let mut state = WarmupState::Done;
+    for version in YewVersion::ALL {
+        let result = async {
+            let app_dir = compile::canonical_app_dir(version).await?;
+            let code = fs::read_to_string(app_dir.join(DEFAULT_SNIPPET))
+                .await
+                .map_err(crate::errors::ApiError::IoError)?;
+            let _permit = queue.acquire().await?;
+            compile::build(&app_dir, &code, OptLevel::default()).await
+        }
+        .await;
+
+        match result {
+            Ok(output) => {
+                info!(
+                    version = version.name(),
+                    wasm_bytes = output.wasm.len(),
+                    "warm-up compile finished"
+                )
+            }
+            Err(e) => {
+                error!(version = version.name(), %e, "warm-up compile failed");
+                state = WarmupState::Failed;
+            }
+        }
+    }
+    health.set_warmup_state(state);
+}
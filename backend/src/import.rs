@@ -0,0 +1,422 @@
+//! Loading code from GitHub gists and raw URLs. The frontend cannot fetch those itself because
+//! of CORS, so the backend proxies them, only from allowed hosts and with a size limit.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use reqwest::{StatusCode, Url, redirect};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::config::ImportConfig;
+use crate::errors::ApiError;
+
+/// Gist API responses carry the content of every file, escaped, so they get more room than the
+/// files themselves.
+const API_SIZE_FACTOR: usize = 4;
+const MAX_REDIRECTS: usize = 5;
+
+/// The project files found at the imported location. `code` is the app's `main.rs`.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct Imported {
+    pub code: String,
+    pub head: String,
+    pub body: String,
+    pub css: String,
+}
+
+#[derive(Deserialize)]
+struct Gist {
+    files: BTreeMap<String, GistFile>,
+}
+
+#[derive(Deserialize)]
+struct GistFile {
+    content: Option<String>,
+    #[serde(default)]
+    truncated: bool,
+    raw_url: String,
+}
+
+pub struct Importer {
+    client: reqwest::Client,
+    allowed_hosts: Arc<Vec<String>>,
+    github_api: Url,
+    github_token: Option<String>,
+    max_size: usize,
+}
+
+fn is_allowed(allowed_hosts: &[String], url: &Url) -> bool {
+    matches!(url.scheme(), "http" | "https")
+        && url
+            .host_str()
+            .is_some_and(|host| allowed_hosts.iter().any(|it| it == host))
+}
+
+impl Importer {
+    pub fn new(config: &ImportConfig) -> anyhow::Result<Self> {
+        let github_api = Url::parse(&config.github_api)?;
+        // the API is not among the allowed hosts, `?url=` would reach all of it with the token
+        let allowed_hosts = Arc::new(config.allowed_hosts.clone());
+
+        // a redirect must not lead away from the allowed hosts, or from the API for its requests
+        let policy = redirect::Policy::custom({
+            let allowed_hosts = allowed_hosts.clone();
+            let api = github_api.origin();
+            move |attempt| {
+                let from_api = attempt.previous().first().map(Url::origin).as_ref() == Some(&api);
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if is_allowed(&allowed_hosts, attempt.url())
+                    || (from_api && attempt.url().origin() == api)
+                {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }
+        });
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("yew-playground/", env!("CARGO_PKG_VERSION")))
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .redirect(policy)
+            .build()?;
+
+        Ok(Importer {
+            client,
+            allowed_hosts,
+            github_api,
+            github_token: Some(config.github_token.clone()).filter(|it| !it.is_empty()),
+            max_size: config.max_size,
+        })
+    }
+
+    /// Fetches `url` from one of the allowed hosts as text, reading at most `max_size` bytes.
+    async fn fetch_allowed(&self, url: Url, max_size: usize) -> Result<String, ApiError> {
+        if !is_allowed(&self.allowed_hosts, &url) {
+            return Err(ApiError::InvalidInput(format!(
+                "importing from {} is not allowed",
+                url.host_str().unwrap_or(url.as_str())
+            )));
+        }
+        self.fetch(self.client.get(url.clone()), &url, max_size)
+            .await
+    }
+
+    /// Sends `request` for `url` and reads the response as text, at most `max_size` bytes.
+    async fn fetch(
+        &self,
+        request: reqwest::RequestBuilder,
+        url: &Url,
+        max_size: usize,
+    ) -> Result<String, ApiError> {
+        debug!(%url, "importing");
+        let request_error = |e: reqwest::Error| {
+            if e.is_timeout() {
+                ApiError::Timeout
+            } else {
+                ApiError::Unknown(anyhow!("failed to fetch {url}: {e}"))
+            }
+        };
+        let mut resp = request.send().await.map_err(request_error)?;
+        match resp.status() {
+            status if status.is_success() => {}
+            StatusCode::NOT_FOUND => {
+                return Err(ApiError::NotFound(format!("{url} was not found")));
+            }
+            // GitHub answers anonymous clients over their limit with 403
+            StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => {
+                return Err(ApiError::RateLimited(format!(
+                    "{} is limiting requests, try again later",
+                    url.host_str().unwrap_or(url.as_str())
+                )));
+            }
+            // the redirect policy stops at hosts that are not allowed
+            status if status.is_redirection() => {
+                return Err(ApiError::InvalidInput(format!(
+                    "{url} redirects to a host importing from is not allowed"
+                )));
+            }
+            status => {
+                return Err(ApiError::Unknown(anyhow!(
+                    "fetching {url} failed with status {status}"
+                )));
+            }
+        }
+
+        let too_large = || ApiError::TooLarge(format!("{url} is larger than {max_size} bytes"));
+        if resp.content_length().is_some_and(|it| it > max_size as u64) {
+            return Err(too_large());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = resp.chunk().await.map_err(request_error)? {
+            if body.len() + chunk.len() > max_size {
+                return Err(too_large());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        String::from_utf8(body).map_err(|_| ApiError::InvalidInput(format!("{url} is not text")))
+    }
+
+    pub async fn url(&self, url: &str) -> Result<Imported, ApiError> {
+        let url = Url::parse(url)
+            .map_err(|e| ApiError::InvalidInput(format!("`{url}` is not a URL: {e}")))?;
+        Ok(Imported {
+            code: self.fetch_allowed(url, self.max_size).await?,
+            ..Imported::default()
+        })
+    }
+
+    /// Imports a gist. Its `main.rs`, or else its first `.rs` file, becomes the code, and
+    /// files named like the playground's other tabs fill those.
+    pub async fn gist(&self, id: &str) -> Result<Imported, ApiError> {
+        if id.is_empty() || id.len() > 64 || !id.bytes().all(|it| it.is_ascii_alphanumeric()) {
+            return Err(ApiError::InvalidInput(format!("`{id}` is not a gist id")));
+        }
+        let url = self
+            .github_api
+            .join(&format!("gists/{id}"))
+            .map_err(anyhow::Error::from)?;
+        if url.origin() != self.github_api.origin() {
+            return Err(ApiError::InvalidInput(format!("`{id}` is not a gist id")));
+        }
+        // the token only ever goes with this request, the API is reached no other way
+        let mut request = self.client.get(url.clone());
+        if let Some(token) = &self.github_token {
+            request = request.bearer_auth(token);
+        }
+        let json = self
+            .fetch(request, &url, self.max_size * API_SIZE_FACTOR)
+            .await?;
+        let gist: Gist = serde_json::from_str(&json)
+            .map_err(|e| ApiError::Unknown(anyhow!("gist {id} is not valid: {e}")))?;
+
+        let code_file = if gist.files.contains_key("main.rs") {
+            "main.rs"
+        } else {
+            gist.files
+                .keys()
+                .find(|it| it.ends_with(".rs"))
+                .ok_or_else(|| ApiError::InvalidInput(format!("gist {id} has no Rust file")))?
+        };
+        let mut imported = Imported::default();
+        for (name, file) in &gist.files {
+            let target = match name.as_str() {
+                name if name == code_file => &mut imported.code,
+                "head.html" => &mut imported.head,
+                "body.html" => &mut imported.body,
+                "style.css" => &mut imported.css,
+                _ => continue,
+            };
+            *target = match &file.content {
+                Some(content) if !file.truncated => content.clone(),
+                _ => {
+                    let url = Url::parse(&file.raw_url).map_err(anyhow::Error::from)?;
+                    self.fetch_allowed(url, self.max_size).await?
+                }
+            };
+        }
+        Ok(imported)
+    }
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    gist: Option<String>,
+    url: Option<String>,
+}
+
+pub fn router(importer: Arc<Importer>) -> Router {
+    Router::new()
+        .route("/import", get(import))
+        .with_state(importer)
+}
+
+async fn import(
+    State(importer): State<Arc<Importer>>,
+    query: Result<Query<ImportQuery>, QueryRejection>,
+) -> Result<Json<Imported>, ApiError> {
+    let Query(query) = query.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    let imported = match (query.gist, query.url) {
+        (Some(id), None) => importer.gist(&id).await?,
+        (None, Some(url)) => importer.url(&url).await?,
+        _ => {
+            return Err(ApiError::InvalidInput(
+                "exactly one of `gist` and `url` must be given".to_string(),
+            ));
+        }
+    };
+    Ok(Json(imported))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::header;
+    use axum::response::{IntoResponse, Redirect};
+    use serde_json::json;
+
+    use super::*;
+
+    /// Serves a gist and raw files on localhost, standing in for GitHub. Returns the base URLs of
+    /// the API and of the raw files, which differ in their host like GitHub's do.
+    async fn stand_in() -> (String, String) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let raw_url = format!("http://localhost:{port}/raw/style.css");
+        let app = Router::new()
+            .route(
+                "/gists/abc123",
+                get(move || async move {
+                    Json(json!({
+                        "files": {
+                            "lib.rs": { "content": "// not this one", "raw_url": "" },
+                            "main.rs": { "content": "fn main() {}", "raw_url": "" },
+                            "style.css": { "content": "body", "truncated": true, "raw_url": raw_url },
+                            "notes.md": { "content": "hi", "raw_url": "" },
+                        }
+                    }))
+                }),
+            )
+            .route(
+                "/gists/private1",
+                get(|headers: header::HeaderMap| async move {
+                    match headers.get(header::AUTHORIZATION) {
+                        Some(it) if it == "Bearer secret" => Json(json!({
+                            "files": { "main.rs": { "content": "fn main() {}", "raw_url": "" } }
+                        }))
+                        .into_response(),
+                        _ => StatusCode::FORBIDDEN.into_response(),
+                    }
+                }),
+            )
+            .route("/gists/busy", get(|| async { StatusCode::TOO_MANY_REQUESTS }))
+            .route(
+                "/raw/away.rs",
+                get(|| async { Redirect::temporary("https://example.com/main.rs") }),
+            )
+            .route(
+                "/raw/style.css",
+                get(|headers: header::HeaderMap| async move {
+                    // the token is only for the API
+                    assert!(!headers.contains_key(header::AUTHORIZATION));
+                    "body { margin: 0 }"
+                }),
+            )
+            .route(
+                "/raw/big.rs",
+                get(|| async { ([(header::CONTENT_TYPE, "text/plain")], "x".repeat(2048)).into_response() }),
+            );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (
+            format!("http://127.0.0.1:{port}"),
+            format!("http://localhost:{port}"),
+        )
+    }
+
+    fn importer(api: &str) -> Importer {
+        importer_with_token(api, "")
+    }
+
+    fn importer_with_token(api: &str, token: &str) -> Importer {
+        Importer::new(&ImportConfig {
+            allowed_hosts: vec!["localhost".to_string()],
+            github_api: format!("{api}/"),
+            github_token: token.to_string(),
+            max_size: 1024,
+            timeout_secs: 5,
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn imports_gists() {
+        let (api, _) = stand_in().await;
+        let imported = importer_with_token(&api, "secret")
+            .gist("abc123")
+            .await
+            .unwrap();
+        assert_eq!(imported.code, "fn main() {}");
+        // truncated files are fetched from their raw URL
+        assert_eq!(imported.css, "body { margin: 0 }");
+        assert!(matches!(
+            importer(&api).gist("missing").await,
+            Err(ApiError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn reports_rate_limits() {
+        let (api, _) = stand_in().await;
+        // without the token the stand-in answers like GitHub over its anonymous limit
+        assert!(matches!(
+            importer(&api).gist("private1").await,
+            Err(ApiError::RateLimited(_))
+        ));
+        let imported = importer_with_token(&api, "secret")
+            .gist("private1")
+            .await
+            .unwrap();
+        assert_eq!(imported.code, "fn main() {}");
+        assert!(matches!(
+            importer(&api).gist("busy").await,
+            Err(ApiError::RateLimited(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_queries_as_json() {
+        let (api, _) = stand_in().await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(Arc::new(importer(&api)));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let resp = reqwest::get(format!("http://{addr}/import?gist=a&gist=b"))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = serde_json::from_slice(&resp.bytes().await.unwrap()).unwrap();
+        assert_eq!(body["kind"], "invalid_input");
+    }
+
+    #[tokio::test]
+    async fn enforces_limits() {
+        let (api, base) = stand_in().await;
+        let importer = importer_with_token(&api, "secret");
+        assert_eq!(
+            importer
+                .url(&format!("{base}/raw/style.css"))
+                .await
+                .unwrap()
+                .code,
+            "body { margin: 0 }"
+        );
+        assert!(matches!(
+            importer.url(&format!("{base}/raw/big.rs")).await,
+            Err(ApiError::TooLarge(_))
+        ));
+        assert!(matches!(
+            importer.url("https://example.com/main.rs").await,
+            Err(ApiError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            importer.url(&format!("{base}/raw/away.rs")).await,
+            Err(ApiError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            importer.gist("../etc").await,
+            Err(ApiError::InvalidInput(_))
+        ));
+        // the API is only used for gists, not as an arbitrary URL with the server's token
+        assert!(matches!(
+            importer.url(&format!("{api}/gists/private1")).await,
+            Err(ApiError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,743 @@
+//! rust-analyzer sessions for the editor, proxied over a websocket.
+//!
+//! Every connection to `/lsp` gets its own rust-analyzer process, working on a copy of the
+//! template crate so Yew and the other template dependencies resolve. Each websocket message
+//! carries one JSON-RPC message. The editor addresses the code as
+//! `file:///workspace/src/main.rs`, which is mapped to the session's directory both ways, so the
+//! server's paths are not exposed. The editor is limited to the methods it needs, and
+//! rust-analyzer's own requests are answered by the proxy, so its configuration stays the
+//! server's. Sessions end when the editor disconnects, after
+//! `lsp.idle_timeout_secs` without a message from it, or when rust-analyzer grows past
+//! `lsp.memory_limit_mb`. Only pages from `cors.origins` may connect, and each client address
+//! may only hold `lsp.max_sessions_per_client` of the `lsp.max_sessions`.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::rejection::QueryRejection;
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code};
+use axum::extract::{ConnectInfo, Query, State};
+use axum::http::{HeaderMap, header};
+use axum::response::Response;
+use axum::routing::get;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::fs;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::process::{Child, Command};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+use tracing::{debug, error, info};
+
+use crate::compile::YewVersion;
+use crate::config::config;
+use crate::errors::ApiError;
+
+/// The workspace root as the editor sees it.
+const CLIENT_ROOT: &str = "file:///workspace";
+/// The code as the editor sees it, the only document it may address.
+const MAIN_URI: &str = "file:///workspace/src/main.rs";
+/// Largest JSON-RPC message accepted from rust-analyzer.
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+const MEMORY_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// `/proc/{pid}/statm` counts pages, which are 4 KiB on the platforms the backend runs on.
+const PAGE_SIZE: u64 = 4096;
+
+/// Distinguishes the directories of concurrent sessions.
+static SESSIONS: AtomicU64 = AtomicU64::new(0);
+
+pub struct LspSessions {
+    permits: Arc<Semaphore>,
+    /// Sessions per client address.
+    clients: Mutex<HashMap<IpAddr, usize>>,
+}
+
+impl LspSessions {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(max_sessions)),
+            clients: Mutex::default(),
+        }
+    }
+}
+
+/// One of a client's sessions, given back when dropped.
+struct ClientSlot {
+    sessions: Arc<LspSessions>,
+    client: IpAddr,
+}
+
+impl ClientSlot {
+    fn acquire(sessions: &Arc<LspSessions>, client: IpAddr, max: usize) -> Option<Self> {
+        let mut clients = sessions.clients.lock().expect("lock is not poisoned");
+        let count = clients.entry(client).or_default();
+        if *count >= max {
+            return None;
+        }
+        *count += 1;
+        Some(Self {
+            sessions: sessions.clone(),
+            client,
+        })
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        let mut clients = self.sessions.clients.lock().expect("lock is not poisoned");
+        if let Some(count) = clients.get_mut(&self.client) {
+            *count -= 1;
+            if *count == 0 {
+                clients.remove(&self.client);
+            }
+        }
+    }
+}
+
+/// The client's address: the connection's peer, or the address the proxy in front of the
+/// backend saw when it is trusted.
+fn client_address(headers: &HeaderMap, peer: IpAddr, trust_forwarded_for: bool) -> IpAddr {
+    if !trust_forwarded_for {
+        return peer;
+    }
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|it| it.to_str().ok())
+        .flat_map(|it| it.split(','))
+        .next_back()
+        .and_then(|it| it.trim().parse().ok())
+        .unwrap_or(peer)
+}
+
+#[derive(Deserialize)]
+struct LspQuery {
+    #[serde(default)]
+    version: YewVersion,
+}
+
+pub fn router(sessions: Arc<LspSessions>) -> Router {
+    Router::new()
+        .route("/lsp", get(connect))
+        .with_state(sessions)
+}
+
+async fn connect(
+    State(sessions): State<Arc<LspSessions>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    query: Result<Query<LspQuery>, QueryRejection>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let Query(query) = query.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    let ws = ws.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    if !config().lsp.enabled {
+        return Err(ApiError::NotFound(
+            "language intelligence is not enabled".to_string(),
+        ));
+    }
+    // browsers do not apply CORS to websockets, any page could otherwise use up the sessions
+    if !config().allows_origin(headers.get(header::ORIGIN)) {
+        return Err(ApiError::Forbidden(
+            "this origin is not allowed".to_string(),
+        ));
+    }
+    let client = client_address(&headers, peer.ip(), config().lsp.trust_forwarded_for);
+    let slot = ClientSlot::acquire(&sessions, client, config().lsp.max_sessions_per_client)
+        .ok_or_else(|| {
+            ApiError::RateLimited("too many language server sessions from this client".to_string())
+        })?;
+    let permit = sessions.permits.clone().try_acquire_owned().map_err(|_| {
+        ApiError::RateLimited(
+            "all language server sessions are in use, try again later".to_string(),
+        )
+    })?;
+    // nothing is started before the upgrade, an upgrade that fails or is abandoned leaves no
+    // directory or process behind
+    Ok(ws
+        .max_message_size(MAX_MESSAGE_SIZE)
+        .on_upgrade(move |mut socket| async move {
+            match Session::start(query.version).await {
+                Ok(session) => session.run(socket, permit, slot).await,
+                Err(e) => {
+                    let frame = CloseFrame {
+                        code: close_code::ERROR,
+                        reason: format!("failed to start the session: {e}").into(),
+                    };
+                    let _ = socket.send(Message::Close(Some(frame))).await;
+                }
+            }
+        }))
+}
+
+/// Why a session ended, sent to the editor when the server ends it.
+enum End {
+    Disconnected,
+    Idle,
+    OutOfMemory,
+    ServerExited,
+    Failed(String),
+}
+
+struct Session {
+    dir: PathBuf,
+    /// `file://` URI of `dir`.
+    root: String,
+    /// The settings rust-analyzer runs with, see [`initialization_options`].
+    options: Value,
+    child: Child,
+}
+
+impl Session {
+    /// Copies the template's manifest and code into a scratch directory and starts
+    /// rust-analyzer in it.
+    async fn start(version: YewVersion) -> Result<Self, ApiError> {
+        let io_error = |e| {
+            error!(?e, "failed to prepare lsp session");
+            ApiError::IoError(e)
+        };
+        let id = SESSIONS.fetch_add(1, Ordering::Relaxed);
+        let dir = config()
+            .cache
+            .dir
+            .join("lsp")
+            .join(format!("{}-{id}", std::process::id()));
+        let copied = match copy_template(version.app_dir(), &dir).await {
+            Ok(()) => fs::canonicalize(&dir).await,
+            Err(e) => Err(e),
+        };
+        let dir = match copied {
+            Ok(dir) => dir,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir).await;
+                return Err(io_error(e));
+            }
+        };
+
+        let mut cmd = Command::new(&config().lsp.rust_analyzer_bin);
+        let cmd = cmd
+            .current_dir(&dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        debug!(?cmd, "running command");
+        let child = match cmd.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                error!(?e, "failed to start rust-analyzer");
+                let _ = fs::remove_dir_all(&dir).await;
+                return Err(ApiError::IoError(e));
+            }
+        };
+        info!(id, ?version, "lsp session started");
+
+        Ok(Session {
+            root: format!("file://{}", dir.display()),
+            options: initialization_options(&config().cache.dir.join("lsp").join("target")),
+            dir,
+            child,
+        })
+    }
+
+    async fn run(
+        mut self,
+        mut socket: WebSocket,
+        _permit: OwnedSemaphorePermit,
+        _slot: ClientSlot,
+    ) {
+        let end = self.proxy(&mut socket).await;
+        let reason = match &end {
+            End::Disconnected => None,
+            End::Idle => Some((close_code::AWAY, "the session was idle".to_string())),
+            End::OutOfMemory => Some((
+                close_code::POLICY,
+                "the session used too much memory".to_string(),
+            )),
+            End::ServerExited => Some((close_code::ERROR, "rust-analyzer exited".to_string())),
+            End::Failed(e) => Some((close_code::ERROR, e.clone())),
+        };
+        if let Some((code, reason)) = reason {
+            info!(%reason, "ending lsp session");
+            let frame = CloseFrame {
+                code,
+                reason: reason.into(),
+            };
+            let _ = socket.send(Message::Close(Some(frame))).await;
+        }
+
+        let _ = self.child.kill().await;
+        if let Err(e) = fs::remove_dir_all(&self.dir).await {
+            error!(?e, dir = ?self.dir, "failed to remove lsp session directory");
+        }
+    }
+
+    async fn proxy(&mut self, socket: &mut WebSocket) -> End {
+        let mut stdin = self.child.stdin.take().expect("stdin is piped");
+        let stdout = self.child.stdout.take().expect("stdout is piped");
+        let (tx, mut from_server) = mpsc::channel(64);
+        let reader = tokio::spawn(async move {
+            let mut stdout = BufReader::new(stdout);
+            while let Ok(Some(message)) = read_message(&mut stdout).await {
+                if tx.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let idle_timeout = Duration::from_secs(config().lsp.idle_timeout_secs);
+        let idle = tokio::time::sleep(idle_timeout);
+        tokio::pin!(idle);
+        let mut memory_check = tokio::time::interval(MEMORY_CHECK_INTERVAL);
+
+        let end = loop {
+            tokio::select! {
+                message = socket.recv() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break End::Disconnected,
+                        Some(Ok(_)) => continue,
+                    };
+                    idle.as_mut().reset(tokio::time::Instant::now() + idle_timeout);
+                    match from_client(text.as_str(), &self.root, &self.dir, &self.options) {
+                        Route::Server(message) => {
+                            if let Err(e) = write_message(&mut stdin, &message).await {
+                                break End::Failed(format!("failed to write to rust-analyzer: {e}"));
+                            }
+                        }
+                        Route::Client(reply) => {
+                            if socket.send(Message::Text(reply.into())).await.is_err() {
+                                break End::Disconnected;
+                            }
+                        }
+                        Route::Drop => {}
+                    }
+                }
+                message = from_server.recv() => {
+                    let Some(message) = message else {
+                        break End::ServerExited;
+                    };
+                    match from_server_message(&message, &self.root, &self.options) {
+                        Route::Client(message) => {
+                            if socket.send(Message::Text(message.into())).await.is_err() {
+                                break End::Disconnected;
+                            }
+                        }
+                        Route::Server(reply) => {
+                            if let Err(e) = write_message(&mut stdin, &reply).await {
+                                break End::Failed(format!("failed to write to rust-analyzer: {e}"));
+                            }
+                        }
+                        Route::Drop => {}
+                    }
+                }
+                _ = &mut idle => break End::Idle,
+                _ = memory_check.tick() => {
+                    let limit = config().lsp.memory_limit_mb * 1024 * 1024;
+                    if self.child.id().and_then(resident_memory).is_some_and(|it| it > limit) {
+                        break End::OutOfMemory;
+                    }
+                }
+            }
+        };
+        reader.abort();
+        end
+    }
+}
+
+async fn copy_template(template: &Path, dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(dir.join("src")).await?;
+    fs::copy(template.join("Cargo.toml"), dir.join("Cargo.toml")).await?;
+    // keeps the dependencies at the versions the playground compiles with
+    match fs::copy(template.join("Cargo.lock"), dir.join("Cargo.lock")).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    fs::copy(template.join("src/main.rs"), dir.join("src/main.rs")).await?;
+    Ok(())
+}
+
+/// Resident memory of process `pid` in bytes, where `/proc` is available.
+fn resident_memory(pid: u32) -> Option<u64> {
+    let statm = std::fs::read_to_string(format!("/proc/{pid}/statm")).ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * PAGE_SIZE)
+}
+
+/// Settings rust-analyzer is started with, whatever the editor asks for. Apps are checked for
+/// the target they are compiled to, and the build scripts and proc macros rust-analyzer builds
+/// are shared between sessions.
+fn initialization_options(target_dir: &Path) -> Value {
+    json!({
+        "cargo": {
+            "target": "wasm32-unknown-unknown",
+            "targetDir": target_dir,
+        },
+        "check": { "allTargets": false },
+    })
+}
+
+/// Requests the editor may send. Anything else could reconfigure rust-analyzer, which runs
+/// configured commands such as `check.overrideCommand` on the backend.
+const CLIENT_REQUESTS: &[&str] = &[
+    "initialize",
+    "shutdown",
+    "textDocument/completion",
+    "completionItem/resolve",
+    "textDocument/hover",
+    "textDocument/definition",
+    "textDocument/diagnostic",
+];
+/// Notifications the editor may send, the rest are dropped.
+const CLIENT_NOTIFICATIONS: &[&str] = &[
+    "initialized",
+    "exit",
+    "$/cancelRequest",
+    "textDocument/didOpen",
+    "textDocument/didChange",
+    "textDocument/didClose",
+];
+/// JSON-RPC error code for requests that are not allowed.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for requests about documents other than the code.
+const INVALID_PARAMS: i64 = -32602;
+
+/// Where a message goes.
+#[derive(Debug, PartialEq)]
+enum Route {
+    /// To rust-analyzer.
+    Server(String),
+    /// To the editor.
+    Client(String),
+    Drop,
+}
+
+/// Routes a message from the editor. Only the methods the editor uses reach rust-analyzer, and
+/// only for `file:///workspace/src/main.rs`, which is mapped to the session's directory. The
+/// `initialize` request is pointed at that directory and given the server's settings.
+fn from_client(message: &str, root: &str, dir: &Path, options: &Value) -> Route {
+    let Ok(mut value) = serde_json::from_str::<Value>(message) else {
+        return Route::Drop;
+    };
+    // responses are only ever owed to rust-analyzer's requests, which the proxy answers itself
+    let Some(method) = value["method"].as_str().map(str::to_string) else {
+        return Route::Drop;
+    };
+    let id = value.get("id").cloned();
+    let reject = |code: i64, message: &str| match &id {
+        Some(id) => Route::Client(
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            })
+            .to_string(),
+        ),
+        None => Route::Drop,
+    };
+    let allowed = match id {
+        Some(_) => CLIENT_REQUESTS.contains(&method.as_str()),
+        None => CLIENT_NOTIFICATIONS.contains(&method.as_str()),
+    };
+    if !allowed {
+        return reject(METHOD_NOT_FOUND, "method is not allowed");
+    }
+
+    let main = format!("{root}/src/main.rs");
+    if let Some(params) = value.get_mut("params")
+        && !map_uris(params, &main)
+    {
+        return reject(INVALID_PARAMS, "only the code can be addressed");
+    }
+    if method == "initialize" {
+        let Some(params) = value.get_mut("params").and_then(Value::as_object_mut) else {
+            return reject(INVALID_PARAMS, "initialize takes an object");
+        };
+        params.insert("rootUri".to_string(), json!(root));
+        params.insert("rootPath".to_string(), json!(dir));
+        params.insert(
+            "workspaceFolders".to_string(),
+            json!([{ "uri": root, "name": "workspace" }]),
+        );
+        params.insert("initializationOptions".to_string(), options.clone());
+    }
+    Route::Server(value.to_string())
+}
+
+/// Maps every `uri` in `value` to `main`, false if one points anywhere but the code.
+fn map_uris(value: &mut Value, main: &str) -> bool {
+    match value {
+        Value::Object(object) => object.iter_mut().all(|(key, value)| match value {
+            Value::String(uri) if key == "uri" || key == "rootUri" => {
+                if uri != MAIN_URI && uri != CLIENT_ROOT {
+                    return false;
+                }
+                if uri == MAIN_URI {
+                    *uri = main.to_string();
+                }
+                true
+            }
+            value => map_uris(value, main),
+        }),
+        Value::Array(values) => values.iter_mut().all(|value| map_uris(value, main)),
+        _ => true,
+    }
+}
+
+/// Routes a message from rust-analyzer. Its requests are answered here, so the editor has no
+/// say in its configuration: `workspace/configuration` gets the session's `options`, the
+/// rest, such as progress and capability registration, only need acknowledging.
+fn from_server_message(message: &str, root: &str, options: &Value) -> Route {
+    if let Ok(value) = serde_json::from_str::<Value>(message)
+        && let (Some(method), Some(id)) = (value["method"].as_str(), value.get("id"))
+    {
+        let result = match method {
+            "workspace/configuration" => {
+                let items = value["params"]["items"].as_array().map_or(0, Vec::len);
+                json!(vec![options; items])
+            }
+            _ => Value::Null,
+        };
+        return Route::Server(json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string());
+    }
+    Route::Client(message.replace(root, CLIENT_ROOT))
+}
+
+/// Reads one message framed with a `Content-Length` header, `None` at the end of the stream.
+async fn read_message(reader: &mut (impl AsyncBufRead + Unpin)) -> io::Result<Option<String>> {
+    let mut content_length = None;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let content_length = content_length.ok_or_else(|| invalid("missing content length"))?;
+    if content_length > MAX_MESSAGE_SIZE {
+        return Err(invalid("message too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| invalid("message is not UTF-8"))
+}
+
+async fn write_message(writer: &mut (impl AsyncWrite + Unpin), message: &str) -> io::Result<()> {
+    let header = format!("Content-Length: {}\r\n\r\n", message.len());
+    writer.write_all(header.as_bytes()).await?;
+    writer.write_all(message.as_bytes()).await?;
+    writer.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn frames_messages() {
+        let mut framed = Vec::new();
+        write_message(&mut framed, r#"{"id":1}"#).await.unwrap();
+        write_message(&mut framed, "{\"text\":\"é\"}")
+            .await
+            .unwrap();
+        assert!(framed.starts_with(b"Content-Length: 8\r\n\r\n"));
+
+        let mut reader = BufReader::new(framed.as_slice());
+        assert_eq!(
+            read_message(&mut reader).await.unwrap().as_deref(),
+            Some(r#"{"id":1}"#)
+        );
+        assert_eq!(
+            read_message(&mut reader).await.unwrap().as_deref(),
+            Some("{\"text\":\"é\"}")
+        );
+        assert_eq!(read_message(&mut reader).await.unwrap(), None);
+    }
+
+    const ROOT: &str = "file:///tmp/lsp/1-0";
+
+    #[test]
+    fn limits_sessions_per_client() {
+        let sessions = Arc::new(LspSessions::new(4));
+        let client: IpAddr = "203.0.113.7".parse().unwrap();
+        let other: IpAddr = "203.0.113.8".parse().unwrap();
+        let first = ClientSlot::acquire(&sessions, client, 2).unwrap();
+        let _second = ClientSlot::acquire(&sessions, client, 2).unwrap();
+        assert!(ClientSlot::acquire(&sessions, client, 2).is_none());
+        assert!(ClientSlot::acquire(&sessions, other, 2).is_some());
+        drop(first);
+        assert!(ClientSlot::acquire(&sessions, client, 2).is_some());
+    }
+
+    #[test]
+    fn identifies_clients() {
+        let peer: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 203.0.113.7".parse().unwrap());
+        // the first entries are whatever the client sent
+        assert_eq!(
+            client_address(&headers, peer, true),
+            "203.0.113.7".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(client_address(&headers, peer, false), peer);
+        assert_eq!(client_address(&HeaderMap::new(), peer, true), peer);
+    }
+
+    fn from_editor(message: Value) -> Route {
+        from_client(
+            &message.to_string(),
+            ROOT,
+            Path::new("/tmp/lsp/1-0"),
+            &options(),
+        )
+    }
+
+    fn options() -> Value {
+        initialization_options(Path::new("/tmp/lsp/target"))
+    }
+
+    #[test]
+    fn maps_the_workspace() {
+        let did_open = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": MAIN_URI, "text": "file:///workspace" } },
+        });
+        let Route::Server(mapped) = from_editor(did_open) else {
+            panic!("didOpen is forwarded");
+        };
+        let mapped: Value = serde_json::from_str(&mapped).unwrap();
+        assert_eq!(
+            mapped["params"]["textDocument"],
+            json!({ "uri": format!("{ROOT}/src/main.rs"), "text": "file:///workspace" })
+        );
+
+        let diagnostics = r#"{"params":{"uri":"file:///tmp/lsp/1-0/src/main.rs"}}"#;
+        assert_eq!(
+            from_server_message(diagnostics, ROOT, &options()),
+            Route::Client(r#"{"params":{"uri":"file:///workspace/src/main.rs"}}"#.to_string())
+        );
+    }
+
+    #[test]
+    fn initializes_with_the_server_settings() {
+        let initialize = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": {
+                "rootUri": CLIENT_ROOT,
+                "initializationOptions": { "check": { "overrideCommand": ["sh", "-c", "id"] } },
+            },
+        });
+        let Route::Server(mapped) = from_editor(initialize) else {
+            panic!("initialize is forwarded");
+        };
+        let mapped: Value = serde_json::from_str(&mapped).unwrap();
+        assert_eq!(mapped["params"]["rootUri"], ROOT);
+        assert_eq!(mapped["params"]["initializationOptions"], options());
+    }
+
+    #[test]
+    fn only_lets_the_editor_use_its_methods() {
+        let configure = json!({
+            "jsonrpc": "2.0",
+            "method": "workspace/didChangeConfiguration",
+            "params": { "settings": { "check": { "overrideCommand": ["sh", "-c", "id"] } } },
+        });
+        assert_eq!(from_editor(configure), Route::Drop);
+
+        let execute = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "workspace/executeCommand",
+            "params": { "command": "rust-analyzer.runSingle" },
+        });
+        let Route::Client(reply) = from_editor(execute) else {
+            panic!("disallowed requests are answered");
+        };
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], 7);
+        assert_eq!(reply["error"]["code"], METHOD_NOT_FOUND);
+
+        // answering rust-analyzer's configuration request is left to the proxy
+        let answer = json!({ "jsonrpc": "2.0", "id": 0, "result": [{ "check": {} }] });
+        assert_eq!(from_editor(answer), Route::Drop);
+    }
+
+    #[test]
+    fn only_lets_the_editor_address_the_code() {
+        for uri in [
+            "file:///workspace/Cargo.toml",
+            "file:///workspace/src/../build.rs",
+            "file:///etc/passwd",
+        ] {
+            let hover = json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "textDocument/hover",
+                "params": { "textDocument": { "uri": uri }, "position": { "line": 0, "character": 0 } },
+            });
+            let Route::Client(reply) = from_editor(hover) else {
+                panic!("{uri} is rejected");
+            };
+            let reply: Value = serde_json::from_str(&reply).unwrap();
+            assert_eq!(reply["error"]["code"], INVALID_PARAMS);
+        }
+    }
+
+    #[test]
+    fn answers_server_requests() {
+        let configuration = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "workspace/configuration",
+            "params": { "items": [{ "section": "rust-analyzer" }] },
+        });
+        let Route::Server(reply) =
+            from_server_message(&configuration.to_string(), ROOT, &options())
+        else {
+            panic!("configuration is answered by the proxy");
+        };
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], 3);
+        assert_eq!(reply["result"], json!([options()]));
+
+        let progress = json!({
+            "jsonrpc": "2.0",
+            "id": 4,
+            "method": "window/workDoneProgress/create",
+            "params": { "token": "x" },
+        });
+        let Route::Server(reply) = from_server_message(&progress.to_string(), ROOT, &options())
+        else {
+            panic!("requests are answered by the proxy");
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&reply).unwrap()["result"],
+            Value::Null
+        );
+    }
+}
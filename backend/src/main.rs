@@ -1,182 +1,258 @@
-use std::sync::LazyLock;
-use std::time::Duration;
-
-use anyhow::anyhow;
-use axum::Router;
-use axum::error_handling::HandleErrorLayer;
-use axum::extract::Query;
-use axum::response::Html;
-use axum::routing::get;
-use serde::Deserialize;
-use tokio::fs;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::rejection::{JsonRejection, QueryRejection};
+use axum::extract::{FromRef, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::Parser;
+use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
 use tokio::process::Command;
-use tower::ServiceBuilder;
-use tower::limit::GlobalConcurrencyLimitLayer;
-use tower_http::cors::CorsLayer;
+use tower_http::compression::CompressionLayer;
 use tower_http::trace::TraceLayer;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
+mod analysis;
+mod artifacts;
+mod compile;
+mod config;
 mod errors;
+mod export;
+mod format;
+mod gcloud;
+mod glue;
+mod health;
+mod import;
+mod lsp;
+mod metrics;
+mod page;
+mod queue;
+mod share;
+mod storage;
+mod tailwind;
 
-use errors::{ApiError, timeout_or_500};
+use analysis::SizeReport;
+use artifacts::ArtifactStore;
+use compile::{BuildOutput, OptLevel, YewVersion};
+use config::config;
+use errors::ApiError;
+use health::Health;
+use metrics::{METRICS, RecordResponse};
+use page::Page;
+use queue::CompileQueue;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-static PORT: LazyLock<u16> = LazyLock::new(|| {
-    std::env::var("PORT")
-        .ok()
-        .and_then(|it| it.parse().ok())
-        .unwrap_or(3000)
-});
-static APP_DIR_STABLE: LazyLock<String> =
-    LazyLock::new(|| std::env::var("APP_DIR_STABLE").unwrap_or_else(|_| "../app".to_string()));
-static APP_DIR_NEXT: LazyLock<String> =
-    LazyLock::new(|| std::env::var("APP_DIR_NEXT").unwrap_or_else(|_| "../app-next".to_string()));
-static TRUNK_BIN: LazyLock<String> =
-    LazyLock::new(|| std::env::var("TRUNK_BIN").unwrap_or_else(|_| "trunk".to_string()));
-
-#[derive(Deserialize, Default, Clone, Copy)]
-#[serde(rename_all = "lowercase")]
-enum YewVersion {
-    #[default]
-    Stable,
-    Next,
-}
-
 #[derive(Deserialize)]
 struct RunPayload {
     code: String,
     #[serde(default)]
     version: YewVersion,
+    #[serde(default)]
+    opt: OptLevel,
+    /// Include a [`SizeReport`] in the response.
+    #[serde(default)]
+    analyze: bool,
+    /// Generate Tailwind CSS for the classes used in `code`.
+    #[serde(default)]
+    tailwind: bool,
+    #[serde(flatten)]
+    page: Page,
+}
+
+#[derive(Serialize)]
+struct RunResponse {
+    /// Serve the app from `/api/artifacts/{id}/index.html`.
+    id: String,
+    /// Size of `app_bg.wasm` in bytes.
+    wasm_size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size_report: Option<SizeReport>,
 }
 
-const INDEX_HTML: &str = r#"
-<!doctype html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, user-scalable=no, initial-scale=1.0, maximum-scale=1.0, minimum-scale=1.0">
-    <meta http-equiv="X-UA-Compatible" content="ie=edge">
-    <title>Document</title>
-</head>
-<body>
-    <script type="module">
-    /*JS_GOES_HERE*/
-    /*INIT_GOES_HERE*/
-    </script>
-</body>
-</html>
-"#;
-
-async fn run(Query(body): Query<RunPayload>) -> Result<Html<String>, ApiError> {
+#[derive(Clone)]
+struct AppState {
+    queue: Arc<CompileQueue>,
+    artifacts: Arc<ArtifactStore>,
+    /// [`compile::fingerprint`] of each template.
+    fingerprints: Arc<HashMap<YewVersion, String>>,
+}
+
+impl FromRef<AppState> for Arc<ArtifactStore> {
+    fn from_ref(state: &AppState) -> Self {
+        state.artifacts.clone()
+    }
+}
+
+/// `GET /run`, taking the payload as query parameters. Kept for old clients, the URL length
+/// limits how much code fits.
+async fn run_query(
+    state: State<AppState>,
+    query: Result<Query<RunPayload>, QueryRejection>,
+) -> Result<Json<RunResponse>, ApiError> {
+    let Query(body) = query.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    run(state, body).await
+}
+
+/// `POST /run`, taking the payload as JSON.
+async fn run_json(
+    state: State<AppState>,
+    json: Result<Json<RunPayload>, JsonRejection>,
+) -> Result<Json<RunResponse>, ApiError> {
+    let Json(body) = json.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    run(state, body).await
+}
+
+async fn run(
+    State(state): State<AppState>,
+    body: RunPayload,
+) -> Result<Json<RunResponse>, ApiError> {
     if body.code.is_empty() {
-        return Err(ApiError::NoBody);
+        return Err(ApiError::InvalidInput(
+            "request must have code but none was found".to_string(),
+        ));
     }
 
-    #[cfg(feature = "simulate-delay")]
-    {
-        let delay: u64 = std::env::var("SIMULATE_DELAY_SECS")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(5);
-        info!(delay, "simulating cold start delay");
-        tokio::time::sleep(Duration::from_secs(delay)).await;
+    let id = ArtifactStore::id(&(
+        body.version,
+        body.opt,
+        body.tailwind,
+        &body.code,
+        &body.page,
+        &state.fingerprints[&body.version],
+    ));
+    let cached = state.artifacts.wasm_size(&id).await;
+    METRICS.record_cache_lookup(cached.is_some());
+    if let Some(wasm_size) = cached {
+        debug!(id, "serving compiled artifact from cache");
+        let size_report = if body.analyze {
+            let wasm = state
+                .artifacts
+                .read(&id, artifacts::APP_WASM)
+                .await
+                .map_err(|e| {
+                    error!(?e, "failed to read artifact");
+                    ApiError::IoError(e)
+                })?;
+            size_report(wasm).await
+        } else {
+            None
+        };
+        return Ok(Json(RunResponse {
+            id,
+            wasm_size,
+            size_report,
+        }));
     }
 
-    let app_dir_str = match body.version {
-        YewVersion::Stable => &*APP_DIR_STABLE,
-        YewVersion::Next => &*APP_DIR_NEXT,
-    };
-    let app_dir = fs::canonicalize(app_dir_str).await.map_err(|e| {
-        error!(?e, "failed to canonicalize app_dir path");
-        ApiError::IoError(e)
+    let _permit = state.queue.acquire().await?;
+    let app_dir = compile::canonical_app_dir(body.version).await?;
+    let started = Instant::now();
+    let result = tokio::time::timeout(config().compile_timeout(), async {
+        // counts towards the timeout, as a slow cold start would
+        #[cfg(feature = "simulate-delay")]
+        {
+            let delay: u64 = std::env::var("SIMULATE_DELAY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5);
+            info!(delay, "simulating cold start delay");
+            tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+        }
+        compile::build(&app_dir, &body.code, body.opt).await
+    })
+    .await
+    .unwrap_or(Err(ApiError::Timeout));
+    METRICS.record_compile(body.version, body.opt, &result, started.elapsed());
+    let BuildOutput { js, wasm } = result?;
+
+    let glue = glue::inspect(&js).map_err(|e| {
+        error!(?e, "unsupported wasm-bindgen glue");
+        ApiError::Unknown(e.into())
     })?;
+    let bootstrap = glue.bootstrap(
+        &format!("./{}", artifacts::APP_JS),
+        &format!("./{}", artifacts::APP_WASM),
+    );
+    let generated_css = if body.tailwind {
+        tailwind::generate(&body.code).await?
+    } else {
+        String::new()
+    };
+    let index_html = body.page.render(&bootstrap, &generated_css);
 
-    fs::write(app_dir.join("src/main.rs"), &body.code)
+    state
+        .artifacts
+        .store(
+            &id,
+            &[
+                (artifacts::INDEX_HTML, index_html.as_bytes()),
+                (artifacts::APP_JS, js.as_bytes()),
+                (artifacts::APP_WASM, &wasm),
+            ],
+        )
         .await
         .map_err(|e| {
-            error!(?e, "failed to write main.rs");
+            error!(?e, "failed to store artifact");
             ApiError::IoError(e)
         })?;
+    let wasm_size = wasm.len() as u64;
+    let size_report = if body.analyze {
+        size_report(wasm).await
+    } else {
+        None
+    };
+    Ok(Json(RunResponse {
+        id,
+        wasm_size,
+        size_report,
+    }))
+}
 
-    let mut cmd = Command::new(&*TRUNK_BIN);
-    let cmd = cmd
-        .env("CARGO_TERM_COLOR", "always")
-        .arg("--color")
-        .arg("always")
-        .arg("--config")
-        .arg(app_dir.join("Trunk.toml"))
-        .arg("build")
-        .kill_on_drop(true);
-    debug!(?cmd, "running command");
-
-    let output = cmd.output().await.map_err(|e| {
-        error!(?e, "running trunk failed");
-        ApiError::IoError(e)
-    })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let html = anstyle_svg::Term::new().render_html(&stderr);
-        return Ok(Html(html));
-    }
-
-    let dist = app_dir.join("dist");
-    let js = fs::read_to_string(dist.join("app.js")).await.map_err(|e| {
-        error!(?e, "failed to read app.js");
-        ApiError::IoError(e)
-    })?;
-    let wasm = fs::read(dist.join("app_bg.wasm")).await.map_err(|e| {
-        error!(?e, "failed to read app_bg.wasm");
-        ApiError::IoError(e)
-    })?;
-
-    debug!(wasm_bytes = wasm.len(), "compilation successful");
-
-    let init_fn = js
-        .split("export default")
-        .nth(1)
-        .and_then(|it| it.trim().strip_suffix(";"))
-        .or_else(|| {
-            js.lines().rev().find_map(|line| {
-                let line = line.trim();
-                let line = line.strip_prefix("export")?;
-                let line = line.trim().strip_prefix('{')?;
-                let line = line.trim().strip_suffix("};")?;
-                line.split(',').find_map(|part| {
-                    let part = part.trim();
-                    part.strip_suffix("as default").map(|name| name.trim())
-                })
-            })
-        });
-
-    match init_fn {
-        Some(init_fn) => {
-            let index_html = INDEX_HTML.replace("/*JS_GOES_HERE*/", &js);
-            let init = format!("{}((new Int8Array({:?})).buffer)", init_fn, wasm);
-            let index_html = index_html.replace("/*INIT_GOES_HERE*/", &init);
-            Ok(Html(index_html))
+/// Analyzes `wasm` off the async runtime. The report is optional, so failures are only logged.
+async fn size_report(wasm: Vec<u8>) -> Option<SizeReport> {
+    match tokio::task::spawn_blocking(move || analysis::analyze(&wasm)).await {
+        Ok(Ok(report)) => Some(report),
+        Ok(Err(e)) => {
+            error!(?e, "failed to analyze wasm");
+            None
+        }
+        Err(e) => {
+            error!(?e, "wasm analysis panicked");
+            None
         }
-        None => Err(ApiError::Unknown(anyhow!(
-            "failed to find init function as default export in js"
-        ))),
     }
 }
 
 #[tokio::main]
 async fn main() {
+    let cli = config::Cli::parse();
+    let print_config = cli.print_config();
+    let loaded = match cli.load() {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+    };
+    if print_config {
+        print!(
+            "{}",
+            toml::to_string_pretty(&loaded).expect("config serializes to TOML")
+        );
+        return;
+    }
+    config::init(loaded);
+
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "backend=trace,hyper=debug,tower_http=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer().with_ansi(std::env::var("NO_ANSI_LOG").is_err()))
+        .with(tracing_subscriber::EnvFilter::new(&config().log.filter))
+        .with(tracing_subscriber::fmt::layer().with_ansi(config().log.ansi))
         .init();
 
-    let app_dir_stable = &*APP_DIR_STABLE;
-    let app_dir_next = &*APP_DIR_NEXT;
-    let trunk_path = &*TRUNK_BIN;
-    debug!(?app_dir_stable, ?app_dir_next);
+    debug!(config = ?config());
+    let trunk_path = &config().trunk_bin;
 
     let trunk_version = Command::new(trunk_path)
         .arg("--version")
@@ -186,22 +262,106 @@ async fn main() {
         .unwrap_or_else(|_| "failed to get trunk version".to_string());
     debug!(trunk_bin_path = ?trunk_path, trunk_version = ?trunk_version);
 
+    let wasm_opt_version = Command::new(&config().wasm_opt_bin)
+        .arg("--version")
+        .output()
+        .await
+        .map(|v| String::from_utf8_lossy(&v.stdout).trim().to_string())
+        .unwrap_or_else(|_| {
+            "failed to get wasm-opt version, the size opt level is unavailable".to_string()
+        });
+    debug!(wasm_opt_bin_path = ?config().wasm_opt_bin, wasm_opt_version = ?wasm_opt_version);
+
+    let tailwind_version = Command::new(&config().tailwind_bin)
+        .arg("--help")
+        .output()
+        .await
+        .map(|v| {
+            let stdout = String::from_utf8_lossy(&v.stdout);
+            stdout.lines().next().unwrap_or_default().trim().to_string()
+        })
+        .unwrap_or_else(|_| "failed to run tailwind, tailwind runs are unavailable".to_string());
+    debug!(tailwind_bin_path = ?config().tailwind_bin, tailwind_version = ?tailwind_version);
+
+    let rustfmt_version = Command::new(&config().rustfmt_bin)
+        .arg("--version")
+        .output()
+        .await
+        .map(|v| String::from_utf8_lossy(&v.stdout).trim().to_string())
+        .unwrap_or_else(|_| "failed to run rustfmt, formatting is unavailable".to_string());
+    debug!(rustfmt_bin_path = ?config().rustfmt_bin, rustfmt_version = ?rustfmt_version);
+
+    if config().lsp.enabled {
+        let rust_analyzer_version = Command::new(&config().lsp.rust_analyzer_bin)
+            .arg("--version")
+            .output()
+            .await
+            .map(|v| String::from_utf8_lossy(&v.stdout).trim().to_string())
+            .unwrap_or_else(|_| "failed to run rust-analyzer, lsp sessions will fail".to_string());
+        debug!(rust_analyzer_bin_path = ?config().lsp.rust_analyzer_bin, rust_analyzer_version = ?rust_analyzer_version);
+    }
+
+    let queue = Arc::new(CompileQueue::new(
+        config().compile.concurrency,
+        config().compile.max_queued,
+    ));
+    let health = Arc::new(Health::new(config().warmup));
+    if config().warmup {
+        tokio::spawn(health::warm_up(health.clone(), queue.clone()));
+    }
+
+    let bucket =
+        storage::Bucket::open(&config().cache).expect("failed to create the storage client");
+    if bucket.is_none() {
+        warn!("no cache.bucket, artifacts can only be served by the instance that built them");
+    }
+    let artifacts = ArtifactStore::open(
+        config().cache.dir.join("artifacts"),
+        config().cache.capacity,
+        bucket,
+    )
+    .await
+    .expect("failed to create the artifact directory");
+    let mut fingerprints = HashMap::new();
+    for version in YewVersion::ALL {
+        fingerprints.insert(version, compile::fingerprint(version).await);
+    }
+    let shares = share::open(&config().share).expect("failed to open the share store");
+    let importer =
+        import::Importer::new(&config().import).expect("failed to create the import client");
+    let state = AppState {
+        queue,
+        artifacts: Arc::new(artifacts),
+        fingerprints: Arc::new(fingerprints),
+    };
+
     let api = Router::new()
-        .route("/run", get(run))
-        .layer(
-            ServiceBuilder::new()
-                .layer(HandleErrorLayer::new(timeout_or_500))
-                .timeout(Duration::from_secs(60)),
-        )
-        .layer(GlobalConcurrencyLimitLayer::new(1))
-        .layer(TraceLayer::new_for_http());
+        .route("/run", get(run_query).post(run_json))
+        .route("/export", post(export::export))
+        .route("/format", post(format::format))
+        .route("/artifacts/{id}/{file}", get(artifacts::serve))
+        .with_state(state)
+        .merge(share::router(shares))
+        .merge(import::router(Arc::new(importer)))
+        .merge(lsp::router(Arc::new(lsp::LspSessions::new(
+            config().lsp.max_sessions,
+        ))))
+        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http().on_response(RecordResponse::default()));
 
     let app = Router::new()
         .nest("/api", api)
-        .layer(CorsLayer::permissive());
+        .merge(health::router(health))
+        .route("/metrics", get(metrics::metrics))
+        .layer(config().cors_layer());
 
-    let addr = format!("0.0.0.0:{}", *PORT);
+    let addr = format!("0.0.0.0:{}", config().port);
     let listener = TcpListener::bind(&addr).await.unwrap();
     info!("Server running on {}", addr);
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
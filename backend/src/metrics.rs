@@ -0,0 +1,168 @@
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::{HeaderValue, Response};
+use axum::response::IntoResponse;
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder, exponential_buckets,
+};
+use tower_http::trace::{DefaultOnResponse, OnResponse};
+use tracing::{Span, error};
+
+use crate::compile::{BuildOutput, OptLevel, YewVersion};
+use crate::errors::ApiError;
+
+pub static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub struct Metrics {
+    registry: Registry,
+    /// Builds by template and outcome (`success`, `compile_failed`, `timeout`, `error`).
+    pub compiles: IntCounterVec,
+    pub compile_duration: HistogramVec,
+    pub compile_timeouts: IntCounter,
+    /// Requests waiting for a compile slot.
+    pub queue_depth: IntGauge,
+    /// Artifact cache lookups by result (`hit` or `miss`).
+    pub cache_lookups: IntCounterVec,
+    pub wasm_size: HistogramVec,
+    pub http_responses: IntCounterVec,
+    pub http_duration: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("playground".to_string()), None)
+            .expect("valid registry prefix");
+
+        let compiles = IntCounterVec::new(
+            Opts::new("compiles_total", "Compilations by template and outcome"),
+            &["template", "outcome"],
+        )
+        .unwrap();
+        let compile_duration = HistogramVec::new(
+            HistogramOpts::new("compile_duration_seconds", "Time spent running trunk")
+                .buckets(vec![1.0, 2.0, 5.0, 10.0, 15.0, 20.0, 30.0, 45.0, 60.0]),
+            &["template"],
+        )
+        .unwrap();
+        let compile_timeouts = IntCounter::new(
+            "compile_timeouts_total",
+            "Compilations cancelled for taking too long",
+        )
+        .unwrap();
+        let queue_depth =
+            IntGauge::new("compile_queue_depth", "Requests waiting for a compile slot").unwrap();
+        let cache_lookups = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Artifact cache lookups by result"),
+            &["result"],
+        )
+        .unwrap();
+        let wasm_size = HistogramVec::new(
+            HistogramOpts::new("wasm_size_bytes", "Size of the compiled app_bg.wasm")
+                .buckets(exponential_buckets(64.0 * 1024.0, 2.0, 8).unwrap()),
+            &["template", "opt"],
+        )
+        .unwrap();
+        let http_responses = IntCounterVec::new(
+            Opts::new("http_responses_total", "HTTP responses by status code"),
+            &["status"],
+        )
+        .unwrap();
+        let http_duration = Histogram::with_opts(HistogramOpts::new(
+            "http_response_duration_seconds",
+            "Time until the response headers were sent",
+        ))
+        .unwrap();
+
+        registry.register(Box::new(compiles.clone())).unwrap();
+        registry
+            .register(Box::new(compile_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(compile_timeouts.clone()))
+            .unwrap();
+        registry.register(Box::new(queue_depth.clone())).unwrap();
+        registry.register(Box::new(cache_lookups.clone())).unwrap();
+        registry.register(Box::new(wasm_size.clone())).unwrap();
+        registry.register(Box::new(http_responses.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+
+        Self {
+            registry,
+            compiles,
+            compile_duration,
+            compile_timeouts,
+            queue_depth,
+            cache_lookups,
+            wasm_size,
+            http_responses,
+            http_duration,
+        }
+    }
+
+    pub fn record_compile(
+        &self,
+        version: YewVersion,
+        opt: OptLevel,
+        result: &Result<BuildOutput, ApiError>,
+        duration: Duration,
+    ) {
+        let template = version.name();
+        let outcome = match result {
+            Ok(output) => {
+                self.wasm_size
+                    .with_label_values(&[template, opt.name()])
+                    .observe(output.wasm.len() as f64);
+                "success"
+            }
+            Err(ApiError::CompileFailed { .. }) => "compile_failed",
+            Err(ApiError::Timeout) => {
+                self.compile_timeouts.inc();
+                "timeout"
+            }
+            Err(_) => "error",
+        };
+        self.compiles.with_label_values(&[template, outcome]).inc();
+        self.compile_duration
+            .with_label_values(&[template])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let result = if hit { "hit" } else { "miss" };
+        self.cache_lookups.with_label_values(&[result]).inc();
+    }
+}
+
+/// `TraceLayer` response hook that counts responses on top of the default logging.
+#[derive(Clone, Default)]
+pub struct RecordResponse(DefaultOnResponse);
+
+impl<B> OnResponse<B> for RecordResponse {
+    fn on_response(self, response: &Response<B>, latency: Duration, span: &Span) {
+        METRICS
+            .http_responses
+            .with_label_values(&[response.status().as_str()])
+            .inc();
+        METRICS.http_duration.observe(latency.as_secs_f64());
+        self.0.on_response(response, latency, span);
+    }
+}
+
+/// Serves all metrics in the Prometheus text format.
+pub async fn metrics() -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let mut buf = Vec::new();
+    if let Err(e) = encoder.encode(&METRICS.registry.gather(), &mut buf) {
+        error!(?e, "failed to encode metrics");
+    }
+    let mut response = Response::new(Body::from(buf));
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(prometheus::TEXT_FORMAT),
+    );
+    response
+}
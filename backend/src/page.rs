@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Title of pages whose head does not set one.
+pub const DEFAULT_TITLE: &str = "Yew App";
+
+/// User supplied parts of the generated `index.html`.
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(default)]
+pub struct Page {
+    /// Extra markup for `<head>`, e.g. `<title>` or `<link>` tags.
+    pub head: String,
+    /// Markup placed in `<body>` before the app is started.
+    pub body: String,
+    /// Stylesheet inlined into the page.
+    pub css: String,
+}
+
+impl Page {
+    /// Renders the page that starts the app with `bootstrap`.
+    ///
+    /// User markup goes in as is, it is the user's own page. The user's head comes before the
+    /// default `<title>`, so a title given there wins. `generated_css` (e.g. Tailwind's output)
+    /// comes before the user's stylesheet so the latter can override it.
+    pub fn render(&self, bootstrap: &str, generated_css: &str) -> String {
+        let Page { head, body, css } = self;
+        // the only thing that can end a <style> element early
+        let css = format!("{generated_css}\n{css}").replace("</style", "<\\/style");
+        format!(
+            r#"<!doctype html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, user-scalable=no, initial-scale=1.0, maximum-scale=1.0, minimum-scale=1.0">
+    <meta http-equiv="X-UA-Compatible" content="ie=edge">
+    {head}
+    <title>{DEFAULT_TITLE}</title>
+    <style>{css}</style>
+</head>
+<body>
+    {body}
+    <script type="module">
+    {bootstrap}
+    </script>
+</body>
+</html>
+"#
+        )
+    }
+}
@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::warn;
+
+use crate::errors::ApiError;
+use crate::metrics::METRICS;
+
+/// Limits how many compilations run at once and how many requests may wait for a free slot.
+///
+/// Requests beyond `max_waiting` are rejected with [`ApiError::QueueFull`] instead of piling up.
+pub struct CompileQueue {
+    permits: Semaphore,
+    waiting: AtomicUsize,
+    max_waiting: usize,
+}
+
+/// Gives the waiting slot back, also when the request is cancelled while queued.
+struct WaitingSlot<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingSlot<'_> {
+    fn drop(&mut self) {
+        let waiting = self.0.fetch_sub(1, Ordering::SeqCst) - 1;
+        METRICS.queue_depth.set(waiting as i64);
+    }
+}
+
+impl CompileQueue {
+    pub fn new(concurrency: usize, max_waiting: usize) -> Self {
+        Self {
+            permits: Semaphore::new(concurrency),
+            waiting: AtomicUsize::new(0),
+            max_waiting,
+        }
+    }
+
+    pub async fn acquire(&self) -> Result<SemaphorePermit<'_>, ApiError> {
+        if let Ok(permit) = self.permits.try_acquire() {
+            return Ok(permit);
+        }
+
+        let waiting = self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _slot = WaitingSlot(&self.waiting);
+        if waiting >= self.max_waiting {
+            warn!(max_waiting = self.max_waiting, "compile queue is full");
+            return Err(ApiError::QueueFull);
+        }
+        METRICS.queue_depth.set(waiting as i64 + 1);
+
+        Ok(self
+            .permits
+            .acquire()
+            .await
+            .expect("compile queue semaphore is never closed"))
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::runtime::Handle;
+
+use super::ShareStore;
+use crate::config::ShareConfig;
+use crate::gcloud::{self, AccessToken};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Shares as documents in a Firestore collection, through its REST API, each holding the share
+/// as a string field `data`. Unlike the other stores this outlives the instance, so it is the
+/// one to use where instances come and go, such as on Cloud Run.
+pub struct FirestoreStore {
+    /// The store is called from blocking threads, requests are run on the server's runtime.
+    runtime: Handle,
+    shares: Collection,
+}
+
+impl FirestoreStore {
+    pub fn open(config: &ShareConfig, runtime: Handle) -> anyhow::Result<Self> {
+        let url = format!(
+            "{}/{}",
+            config.firestore_url.trim_end_matches('/'),
+            config.firestore_collection
+        );
+        Ok(Self {
+            runtime,
+            shares: Collection::new(url, config.firestore_auth)?,
+        })
+    }
+}
+
+impl ShareStore for FirestoreStore {
+    fn insert(&self, id: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let data = std::str::from_utf8(data).context("shares are JSON")?;
+        self.runtime.block_on(self.shares.create(id, "data", data))
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let data = self.runtime.block_on(self.shares.read(id, "data"))?;
+        Ok(data.map(String::into_bytes))
+    }
+}
+
+/// The pastes of the old Firestore based sharing, documents holding the code as a string field
+/// `content`. They are public, so they are read without a token.
+pub struct LegacyPastes {
+    runtime: Handle,
+    pastes: Collection,
+}
+
+impl LegacyPastes {
+    pub fn open(url: &str, runtime: Handle) -> anyhow::Result<Self> {
+        Ok(Self {
+            runtime,
+            pastes: Collection::new(url.trim_end_matches('/').to_string(), false)?,
+        })
+    }
+
+    /// The code of paste `id`, `None` if there is no such paste.
+    pub fn get(&self, id: &str) -> anyhow::Result<Option<String>> {
+        self.runtime.block_on(self.pastes.read(id, "content"))
+    }
+}
+
+/// A collection's documents with string fields, through the REST API.
+struct Collection {
+    client: reqwest::Client,
+    /// The collection's URL, `{documents}/{collection}`.
+    url: String,
+    /// The service account token, `None` when requests are made without one, as for the
+    /// emulator.
+    token: Option<AccessToken>,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    fields: HashMap<String, StringValue>,
+}
+
+/// A field's value, other types (such as the pastes' `createdBy: null`) have no `stringValue`.
+#[derive(Deserialize)]
+struct StringValue {
+    #[serde(rename = "stringValue")]
+    string_value: Option<String>,
+}
+
+impl Collection {
+    fn new(url: String, auth: bool) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("yew-playground/", env!("CARGO_PKG_VERSION")))
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Self {
+            client,
+            url,
+            token: auth.then(AccessToken::default),
+        })
+    }
+
+    async fn request(&self, request: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        let request = gcloud::authorize(request, &self.client, self.token.as_ref()).await?;
+        Ok(request.send().await?)
+    }
+
+    /// Creates document `id` with `value` as `field`. Returns `false` if `id` is taken.
+    async fn create(&self, id: &str, field: &str, value: &str) -> anyhow::Result<bool> {
+        let body = json!({ "fields": { field: { "stringValue": value } } });
+        let resp = self
+            .request(
+                self.client
+                    .post(&self.url)
+                    .query(&[("documentId", id)])
+                    .header("Content-Type", "application/json")
+                    .body(body.to_string()),
+            )
+            .await?;
+        match resp.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::CONFLICT => Ok(false),
+            status => Err(anyhow!(
+                "storing document {id} failed with status {status}: {}",
+                resp.text().await.unwrap_or_default()
+            )),
+        }
+    }
+
+    /// The string `field` of document `id`, `None` if there is no such document.
+    async fn read(&self, id: &str, field: &str) -> anyhow::Result<Option<String>> {
+        let resp = self
+            .request(self.client.get(format!("{}/{id}", self.url)))
+            .await?;
+        match resp.status() {
+            status if status.is_success() => {
+                let mut document: Document = serde_json::from_slice(&resp.bytes().await?)
+                    .with_context(|| format!("document {id} is not valid"))?;
+                let value = document
+                    .fields
+                    .remove(field)
+                    .and_then(|it| it.string_value)
+                    .ok_or_else(|| anyhow!("document {id} has no string field {field}"))?;
+                Ok(Some(value))
+            }
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(anyhow!(
+                "reading document {id} failed with status {status}: {}",
+                resp.text().await.unwrap_or_default()
+            )),
+        }
+    }
+}
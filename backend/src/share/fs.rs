@@ -0,0 +1,53 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use super::ShareStore;
+
+/// Shares as JSON files, `{dir}/{first two id chars}/{id}.json`.
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(&id[..2]).join(format!("{id}.json"))
+    }
+}
+
+impl ShareStore for FsStore {
+    fn insert(&self, id: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let path = self.path(id);
+        let parent = path.parent().expect("share paths have a parent");
+        fs::create_dir_all(parent)?;
+
+        // Written under a scratch name and linked into place, so readers never see a partial
+        // file and an existing share is never overwritten
+        let scratch = parent.join(format!(".{id}.tmp"));
+        let mut file = fs::File::create(&scratch)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        let linked = fs::hard_link(&scratch, &path);
+        fs::remove_file(&scratch)?;
+        match linked {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        match fs::read(self.path(id)) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
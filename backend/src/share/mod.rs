@@ -0,0 +1,585 @@
+//! Shared projects, stored by the backend so share links do not depend on a third-party
+//! service.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::runtime::Handle;
+use tracing::debug;
+
+use crate::compile::{OptLevel, YewVersion};
+use crate::config::{ShareConfig, ShareStoreKind, config};
+use crate::errors::ApiError;
+
+mod firestore;
+mod fs;
+mod sqlite;
+
+pub use firestore::{FirestoreStore, LegacyPastes};
+pub use fs::FsStore;
+pub use sqlite::SqliteStore;
+
+/// Where shares are kept, as opaque bytes under their id.
+///
+/// Implementations block, handlers call them through `spawn_blocking`.
+pub trait ShareStore: Send + Sync {
+    /// Stores `data` under `id`. Returns `false` without storing anything if `id` is taken.
+    fn insert(&self, id: &str, data: &[u8]) -> anyhow::Result<bool>;
+    fn get(&self, id: &str) -> anyhow::Result<Option<Vec<u8>>>;
+}
+
+/// Opens the configured store, which also finds the old Firestore pastes if
+/// `legacy_pastes_url` is set. The Firestore stores run their requests on the current Tokio
+/// runtime.
+pub fn open(config: &ShareConfig) -> anyhow::Result<Arc<dyn ShareStore>> {
+    let store: Arc<dyn ShareStore> = match config.store {
+        ShareStoreKind::Firestore => Arc::new(FirestoreStore::open(config, Handle::current())?),
+        ShareStoreKind::Sqlite => Arc::new(SqliteStore::open(&config.dir)?),
+        ShareStoreKind::Fs => Arc::new(FsStore::open(&config.dir)?),
+    };
+    if config.legacy_pastes_url.is_empty() {
+        return Ok(store);
+    }
+    Ok(Arc::new(WithPastes {
+        store,
+        pastes: LegacyPastes::open(&config.legacy_pastes_url, Handle::current())?,
+    }))
+}
+
+/// A store that also has the pastes of the old Firestore based sharing, under their own ids.
+/// Links to them keep working, and they are read as content-only [`LegacyShare`]s.
+struct WithPastes {
+    store: Arc<dyn ShareStore>,
+    pastes: LegacyPastes,
+}
+
+impl ShareStore for WithPastes {
+    fn insert(&self, id: &str, data: &[u8]) -> anyhow::Result<bool> {
+        self.store.insert(id, data)
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        if !is_legacy_id(id) {
+            return self.store.get(id);
+        }
+        let paste = self.pastes.get(id)?;
+        Ok(paste.map(|content| json!({ "content": content }).to_string().into_bytes()))
+    }
+}
+
+/// Current version of the stored share format. Shares without a `schema` field predate it.
+const SCHEMA: u64 = 2;
+
+/// A shared project, as stored and returned by `GET /share/{id}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Share {
+    pub schema: u64,
+    pub version: YewVersion,
+    pub title: String,
+    pub files: ShareFiles,
+    pub settings: ShareSettings,
+    /// Template dependencies (name to version requirement) the project was shared with, so
+    /// drift can be told apart from the user's own changes.
+    pub dependencies: BTreeMap<String, String>,
+    /// Unix timestamp in seconds, unknown for shares older than the schema.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// The share this one was forked from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareFiles {
+    pub code: String,
+    pub head: String,
+    pub body: String,
+    pub css: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareSettings {
+    pub opt: OptLevel,
+    pub tailwind: bool,
+}
+
+/// Body of `POST /share`, everything but what the backend fills in.
+#[derive(Deserialize)]
+struct NewShare {
+    #[serde(default)]
+    version: YewVersion,
+    #[serde(default)]
+    title: String,
+    files: ShareFiles,
+    #[serde(default)]
+    settings: ShareSettings,
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    parent: Option<String>,
+}
+
+/// Shares stored before [`SCHEMA`]: the project files at the top level, or only the code as
+/// `content` for pastes carried over from the old Firestore based sharing.
+#[derive(Deserialize)]
+struct LegacyShare {
+    #[serde(alias = "content")]
+    code: String,
+    #[serde(default)]
+    head: String,
+    #[serde(default)]
+    body: String,
+    #[serde(default)]
+    css: String,
+}
+
+impl From<LegacyShare> for Share {
+    fn from(legacy: LegacyShare) -> Self {
+        let LegacyShare {
+            code,
+            head,
+            body,
+            css,
+        } = legacy;
+        Share {
+            schema: SCHEMA,
+            version: YewVersion::default(),
+            title: String::new(),
+            files: ShareFiles {
+                code,
+                head,
+                body,
+                css,
+            },
+            settings: ShareSettings::default(),
+            dependencies: BTreeMap::new(),
+            created_at: None,
+            parent: None,
+        }
+    }
+}
+
+/// Maximum length of a share's title, in bytes.
+const MAX_TITLE_LEN: usize = 200;
+
+/// Reads a stored share, migrating older formats to the current one.
+fn decode(data: &[u8]) -> anyhow::Result<Share> {
+    let value: serde_json::Value = serde_json::from_slice(data)?;
+    match value.get("schema").map(serde_json::Value::as_u64) {
+        None => Ok(serde_json::from_value::<LegacyShare>(value)?.into()),
+        Some(Some(SCHEMA)) => Ok(serde_json::from_value(value)?),
+        Some(schema) => Err(anyhow!("unknown share schema {schema:?}")),
+    }
+}
+
+/// Reads and decodes the share stored under `id`.
+fn load(store: &dyn ShareStore, id: &str) -> anyhow::Result<Option<Share>> {
+    let Some(data) = store.get(id)? else {
+        return Ok(None);
+    };
+    let share = decode(&data).map_err(|e| anyhow!("stored share {id} is not valid: {e}"))?;
+    Ok(Some(share))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|it| it.as_secs())
+        .unwrap_or_default()
+}
+
+/// Random bytes in an id, encoded as 12 URL safe characters.
+const ID_BYTES: usize = 9;
+const ID_LEN: usize = 12;
+/// Attempts at finding a free id. With 72 random bits a collision is already unlikely.
+const ID_ATTEMPTS: usize = 5;
+
+fn new_id() -> anyhow::Result<String> {
+    let mut bytes = [0; ID_BYTES];
+    getrandom::fill(&mut bytes).map_err(|e| anyhow!("failed to generate share id: {e}"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Length of the ids Firestore generated for the old pastes.
+const LEGACY_ID_LEN: usize = 20;
+
+/// Ids are also used as file names, so anything else is rejected before reaching a store.
+fn is_valid_id(id: &str) -> bool {
+    is_legacy_id(id)
+        || id.len() == ID_LEN
+            && id
+                .bytes()
+                .all(|it| it.is_ascii_alphanumeric() || it == b'-' || it == b'_')
+}
+
+/// Whether `id` is one of the old Firestore pastes' ids, which are alphanumeric.
+fn is_legacy_id(id: &str) -> bool {
+    id.len() == LEGACY_ID_LEN && id.bytes().all(|it| it.is_ascii_alphanumeric())
+}
+
+#[derive(Serialize)]
+struct CreatedShare {
+    id: String,
+}
+
+/// A share in the lineage of another one.
+#[derive(Debug, PartialEq, Serialize)]
+struct Ancestor {
+    id: String,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_at: Option<u64>,
+}
+
+/// How far back `GET /share/{id}/lineage` follows parents.
+const MAX_LINEAGE: usize = 100;
+
+pub fn router(store: Arc<dyn ShareStore>) -> Router {
+    Router::new()
+        .route("/share", post(create))
+        .route("/share/{id}", get(fetch))
+        .route("/share/{id}/lineage", get(lineage))
+        .with_state(store)
+}
+
+async fn create(
+    State(store): State<Arc<dyn ShareStore>>,
+    json: Result<Json<NewShare>, JsonRejection>,
+) -> Result<(StatusCode, Json<CreatedShare>), ApiError> {
+    let Json(new) = json.map_err(|e| ApiError::InvalidInput(e.body_text()))?;
+    if new.files.code.is_empty() {
+        return Err(ApiError::InvalidInput(
+            "share must have code but none was found".to_string(),
+        ));
+    }
+    if new.title.len() > MAX_TITLE_LEN {
+        return Err(ApiError::InvalidInput(format!(
+            "share title is longer than {MAX_TITLE_LEN} bytes"
+        )));
+    }
+    if let Some(parent) = &new.parent
+        && !is_valid_id(parent)
+    {
+        return Err(ApiError::InvalidInput(format!(
+            "parent {parent} is not a share id"
+        )));
+    }
+    let share = Share {
+        schema: SCHEMA,
+        version: new.version,
+        title: new.title,
+        files: new.files,
+        settings: new.settings,
+        dependencies: new.dependencies,
+        created_at: Some(unix_now()),
+        parent: new.parent,
+    };
+    let data = serde_json::to_vec(&share).map_err(anyhow::Error::from)?;
+    let max_size = config().share.max_size;
+    if data.len() > max_size {
+        return Err(ApiError::TooLarge(format!(
+            "share is larger than the limit of {max_size} bytes"
+        )));
+    }
+
+    let id = tokio::task::spawn_blocking(move || {
+        if let Some(parent) = &share.parent
+            && store.get(parent)?.is_none()
+        {
+            return Err(ApiError::InvalidInput(format!(
+                "parent share {parent} does not exist"
+            )));
+        }
+        for _ in 0..ID_ATTEMPTS {
+            let id = new_id()?;
+            if store.insert(&id, &data)? {
+                return Ok(id);
+            }
+        }
+        Err(anyhow!("failed to find a free share id").into())
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
+    debug!(id, "stored share");
+    Ok((StatusCode::CREATED, Json(CreatedShare { id })))
+}
+
+async fn fetch(
+    State(store): State<Arc<dyn ShareStore>>,
+    Path(id): Path<String>,
+) -> Result<Json<Share>, ApiError> {
+    let not_found = || ApiError::NotFound(format!("no share with id {id}"));
+    if !is_valid_id(&id) {
+        return Err(not_found());
+    }
+
+    let share = tokio::task::spawn_blocking({
+        let id = id.clone();
+        move || load(&*store, &id)
+    })
+    .await
+    .map_err(anyhow::Error::from)??;
+    Ok(Json(share.ok_or_else(not_found)?))
+}
+
+/// The share and the shares it was forked from, newest first.
+async fn lineage(
+    State(store): State<Arc<dyn ShareStore>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<Ancestor>>, ApiError> {
+    if !is_valid_id(&id) {
+        return Err(ApiError::NotFound(format!("no share with id {id}")));
+    }
+
+    let ancestors = tokio::task::spawn_blocking(move || ancestors(&*store, id))
+        .await
+        .map_err(anyhow::Error::from)??;
+    Ok(Json(ancestors))
+}
+
+fn ancestors(store: &dyn ShareStore, id: String) -> Result<Vec<Ancestor>, ApiError> {
+    let mut ancestors = Vec::new();
+    let mut next = Some(id);
+    while let Some(id) = next.take() {
+        if ancestors.len() == MAX_LINEAGE {
+            break;
+        }
+        let Some(share) = load(store, &id)? else {
+            if ancestors.is_empty() {
+                return Err(ApiError::NotFound(format!("no share with id {id}")));
+            }
+            // parents are checked on creation, but stores can be pruned by hand
+            break;
+        };
+        next = share.parent;
+        ancestors.push(Ancestor {
+            id,
+            title: share.title,
+            created_at: share.created_at,
+        });
+    }
+    Ok(ancestors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(store: &dyn ShareStore) {
+        let id = new_id().unwrap();
+        assert!(is_valid_id(&id));
+        assert_eq!(store.get(&id).unwrap(), None);
+        assert!(store.insert(&id, b"first").unwrap());
+        assert!(!store.insert(&id, b"second").unwrap());
+        assert_eq!(store.get(&id).unwrap().as_deref(), Some(&b"first"[..]));
+    }
+
+    fn scratch_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "playground-share-test-{name}-{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn fs_store_round_trip() {
+        let dir = scratch_dir("fs");
+        round_trip(&FsStore::open(&dir).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn sqlite_store_round_trip() {
+        let dir = scratch_dir("sqlite");
+        round_trip(&SqliteStore::open(&dir).unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    /// A Firestore stand-in, answering document creation and reads like the REST API does.
+    async fn firestore_stand_in() -> String {
+        use std::collections::HashMap;
+        use std::sync::Mutex;
+
+        use axum::extract::Query;
+
+        type Documents = Arc<Mutex<HashMap<String, String>>>;
+        let documents = Documents::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = Router::new()
+            .route(
+                "/documents/shares",
+                post(
+                    |State(documents): State<Documents>,
+                     Query(query): Query<HashMap<String, String>>,
+                     body: String| async move {
+                        let mut documents = documents.lock().unwrap();
+                        let id = &query["documentId"];
+                        if documents.contains_key(id) {
+                            return StatusCode::CONFLICT;
+                        }
+                        documents.insert(id.clone(), body);
+                        StatusCode::OK
+                    },
+                ),
+            )
+            .route(
+                "/documents/shares/{id}",
+                get(
+                    |State(documents): State<Documents>, Path(id): Path<String>| async move {
+                        match documents.lock().unwrap().get(&id) {
+                            Some(body) => (StatusCode::OK, body.clone()),
+                            None => (StatusCode::NOT_FOUND, String::new()),
+                        }
+                    },
+                ),
+            )
+            .route(
+                &format!("/documents/pastes/{LEGACY_PASTE}"),
+                // as the old sharing stored them
+                get(|| async {
+                    Json(serde_json::json!({
+                        "name": format!("projects/pastify-app/databases/(default)/documents/pastes/{LEGACY_PASTE}"),
+                        "fields": {
+                            "content": { "stringValue": "fn main() {}" },
+                            "createdBy": { "nullValue": null },
+                        },
+                        "createTime": "2023-04-01T12:00:00.000000Z",
+                        "updateTime": "2023-04-01T12:00:00.000000Z",
+                    }))
+                }),
+            )
+            .with_state(documents);
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/documents")
+    }
+
+    /// An id as Firestore generated them for the old pastes.
+    const LEGACY_PASTE: &str = "8bCqF2sXwZ4mTn0LpRv7";
+
+    #[test]
+    fn firestore_store_round_trip() {
+        // the store blocks on the runtime, so it is called from outside of it
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let url = runtime.block_on(firestore_stand_in());
+        let config = ShareConfig {
+            store: ShareStoreKind::Firestore,
+            firestore_url: url,
+            firestore_auth: false,
+            ..ShareConfig::default()
+        };
+        round_trip(&FirestoreStore::open(&config, runtime.handle().clone()).unwrap());
+    }
+
+    #[test]
+    fn reads_legacy_pastes() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let url = runtime.block_on(firestore_stand_in());
+        let dir = scratch_dir("legacy");
+        let store = WithPastes {
+            store: Arc::new(SqliteStore::open(&dir).unwrap()),
+            pastes: LegacyPastes::open(&format!("{url}/pastes"), runtime.handle().clone()).unwrap(),
+        };
+
+        let paste = load(&store, LEGACY_PASTE).unwrap().unwrap();
+        assert_eq!(paste.files.code, "fn main() {}");
+        assert_eq!(paste.version, YewVersion::Stable);
+        assert_eq!(load(&store, "0000000000000000000a").unwrap(), None);
+        // new shares are still in the store
+        round_trip(&store);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn migrates_legacy_shares() {
+        let files = decode(br#"{"code":"fn main() {}","css":"body {}"}"#).unwrap();
+        assert_eq!(files.schema, SCHEMA);
+        assert_eq!(files.version, YewVersion::Stable);
+        assert_eq!(files.files.code, "fn main() {}");
+        assert_eq!(files.files.css, "body {}");
+        assert_eq!(files.created_at, None);
+
+        let paste = decode(br#"{"content":"fn main() {}"}"#).unwrap();
+        assert_eq!(paste.files.code, "fn main() {}");
+        assert_eq!(paste.files.head, "");
+    }
+
+    #[test]
+    fn decodes_current_shares() {
+        let share = Share {
+            schema: SCHEMA,
+            version: YewVersion::Next,
+            title: "counter".to_string(),
+            files: ShareFiles {
+                code: "fn main() {}".to_string(),
+                ..ShareFiles::default()
+            },
+            settings: ShareSettings {
+                opt: OptLevel::Size,
+                tailwind: true,
+            },
+            dependencies: BTreeMap::from([("yew".to_string(), "0.23".to_string())]),
+            created_at: Some(1_700_000_000),
+            parent: None,
+        };
+        let data = serde_json::to_vec(&share).unwrap();
+        assert_eq!(decode(&data).unwrap(), share);
+        assert!(decode(br#"{"schema":99,"files":{}}"#).is_err());
+    }
+
+    #[test]
+    fn follows_parents_for_lineage() {
+        let dir = scratch_dir("lineage");
+        let store = FsStore::open(&dir).unwrap();
+        let mut parent = None;
+        for id in ["aaaaaaaaaaaa", "bbbbbbbbbbbb", "cccccccccccc"] {
+            let share = serde_json::json!({
+                "schema": SCHEMA,
+                "version": "stable",
+                "title": id,
+                "files": { "code": "fn main() {}" },
+                "settings": {},
+                "dependencies": {},
+                "parent": parent,
+            });
+            assert!(store.insert(id, share.to_string().as_bytes()).unwrap());
+            parent = Some(id);
+        }
+
+        let ids: Vec<_> = ancestors(&store, "cccccccccccc".to_string())
+            .unwrap()
+            .into_iter()
+            .map(|it| it.id)
+            .collect();
+        assert_eq!(ids, ["cccccccccccc", "bbbbbbbbbbbb", "aaaaaaaaaaaa"]);
+        assert!(matches!(
+            ancestors(&store, "dddddddddddd".to_string()),
+            Err(ApiError::NotFound(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_ids_that_are_not_file_names() {
+        assert!(!is_valid_id("../../etc/pw"));
+        assert!(!is_valid_id("short"));
+        assert!(is_valid_id("aZ09-_aZ09-_"));
+        assert!(is_valid_id(LEGACY_PASTE));
+        assert!(!is_valid_id("aZ09-_aZ09-_aZ09-_aZ"));
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{Connection, ErrorCode, OptionalExtension, params};
+
+use super::ShareStore;
+
+/// Shares in a single SQLite database, `{dir}/shares.sqlite3`.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(dir: &Path) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let conn = Connection::open(dir.join("shares.sqlite3"))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS shares (
+                id TEXT PRIMARY KEY NOT NULL,
+                data BLOB NOT NULL,
+                created_at INTEGER NOT NULL DEFAULT (unixepoch())
+            )",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl ShareStore for SqliteStore {
+    fn insert(&self, id: &str, data: &[u8]) -> anyhow::Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        match conn.execute(
+            "INSERT INTO shares (id, data) VALUES (?1, ?2)",
+            params![id, data],
+        ) {
+            Ok(_) => Ok(true),
+            Err(rusqlite::Error::SqliteFailure(e, _))
+                if e.code == ErrorCode::ConstraintViolation =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn get(&self, id: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let conn = self.conn.lock().unwrap();
+        let data = conn
+            .query_row("SELECT data FROM shares WHERE id = ?1", [id], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(data)
+    }
+}
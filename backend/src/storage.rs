@@ -0,0 +1,101 @@
+//! Objects in a Cloud Storage bucket, through its JSON API.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use reqwest::{StatusCode, Url};
+
+use crate::config::CacheConfig;
+use crate::gcloud::{self, AccessToken};
+
+/// Artifacts are a few megabytes, uploads get more time than the other API calls.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct Bucket {
+    client: reqwest::Client,
+    /// The API's base URL, `https://storage.googleapis.com` outside of tests.
+    api: Url,
+    name: String,
+    /// The service account token, `None` when requests are made without one.
+    token: Option<AccessToken>,
+}
+
+impl Bucket {
+    /// The bucket configured for artifacts, `None` if there is none.
+    pub fn open(config: &CacheConfig) -> anyhow::Result<Option<Self>> {
+        if config.bucket.is_empty() {
+            return Ok(None);
+        }
+        let client = reqwest::Client::builder()
+            .user_agent(concat!("yew-playground/", env!("CARGO_PKG_VERSION")))
+            .timeout(REQUEST_TIMEOUT)
+            .build()?;
+        Ok(Some(Self {
+            client,
+            api: Url::parse(&config.storage_url)?,
+            name: config.bucket.clone(),
+            token: config.storage_auth.then(AccessToken::default),
+        }))
+    }
+
+    /// `{api}/{prefix..}/b/{bucket}/o` followed by `object` as a single, escaped segment.
+    fn url(&self, prefix: &[&str], object: Option<&str>) -> Url {
+        let mut url = self.api.clone();
+        {
+            let mut segments = url.path_segments_mut().expect("http(s) URLs have a path");
+            segments.pop_if_empty().extend(prefix);
+            segments.extend(["b", &self.name, "o"]);
+            if let Some(object) = object {
+                segments.push(object);
+            }
+        }
+        url
+    }
+
+    /// Uploads `data` as `object` unless the object exists already. Objects are addressed by
+    /// their contents, so an existing one holds the same bytes.
+    pub async fn put(&self, object: &str, content_type: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        let request = self
+            .client
+            .post(self.url(&["upload", "storage", "v1"], None))
+            .query(&[
+                ("uploadType", "media"),
+                ("name", object),
+                ("ifGenerationMatch", "0"),
+            ])
+            .header("Content-Type", content_type)
+            .body(data);
+        let resp = gcloud::authorize(request, &self.client, self.token.as_ref())
+            .await?
+            .send()
+            .await?;
+        match resp.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::PRECONDITION_FAILED => Ok(()),
+            status => Err(anyhow!(
+                "uploading {object} failed with status {status}: {}",
+                resp.text().await.unwrap_or_default()
+            )),
+        }
+    }
+
+    /// The contents of `object`, `None` if there is no such object.
+    pub async fn get(&self, object: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        let request = self
+            .client
+            .get(self.url(&["storage", "v1"], Some(object)))
+            .query(&[("alt", "media")]);
+        let resp = gcloud::authorize(request, &self.client, self.token.as_ref())
+            .await?
+            .send()
+            .await?;
+        match resp.status() {
+            status if status.is_success() => Ok(Some(resp.bytes().await?.to_vec())),
+            StatusCode::NOT_FOUND => Ok(None),
+            status => Err(anyhow!(
+                "downloading {object} failed with status {status}: {}",
+                resp.text().await.unwrap_or_default()
+            )),
+        }
+    }
+}
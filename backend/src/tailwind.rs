@@ -0,0 +1,81 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::anyhow;
+use tokio::fs;
+use tokio::process::Command;
+use tracing::{debug, error};
+
+use crate::config::config;
+use crate::errors::ApiError;
+
+/// Entry stylesheet. Automatic source detection is disabled so only the user's code is scanned
+/// for class names, not whatever else is in the working directory.
+const INPUT_CSS: &str = r#"@import "tailwindcss" source(none);
+@source "./main.rs";
+"#;
+
+/// Distinguishes the scratch directories of concurrent runs.
+static RUNS: AtomicU64 = AtomicU64::new(0);
+
+/// Generates the Tailwind CSS for the classes used in `code` with the standalone tailwind CLI.
+pub async fn generate(code: &str) -> Result<String, ApiError> {
+    let run = RUNS.fetch_add(1, Ordering::Relaxed);
+    let dir = config()
+        .cache
+        .dir
+        .join("tailwind")
+        .join(format!("{}-{run}", std::process::id()));
+    let result = tokio::time::timeout(config().compile_timeout(), run_tailwind(&dir, code))
+        .await
+        .unwrap_or(Err(ApiError::Timeout));
+    if let Err(e) = fs::remove_dir_all(&dir).await {
+        error!(?e, ?dir, "failed to remove tailwind scratch directory");
+    }
+    result
+}
+
+async fn run_tailwind(dir: &Path, code: &str) -> Result<String, ApiError> {
+    let io_error = |e| {
+        error!(?e, "failed to prepare tailwind input");
+        ApiError::IoError(e)
+    };
+    fs::create_dir_all(dir).await.map_err(io_error)?;
+    fs::write(dir.join("main.rs"), code)
+        .await
+        .map_err(io_error)?;
+    fs::write(dir.join("input.css"), INPUT_CSS)
+        .await
+        .map_err(io_error)?;
+
+    let mut cmd = Command::new(&config().tailwind_bin);
+    let cmd = cmd
+        .current_dir(dir)
+        .arg("--input")
+        .arg("input.css")
+        .arg("--output")
+        .arg("output.css")
+        .arg("--minify")
+        .kill_on_drop(true);
+    debug!(?cmd, "running command");
+
+    let output = cmd.output().await.map_err(|e| {
+        error!(?e, "running tailwind failed");
+        ApiError::IoError(e)
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        error!(%stderr, "tailwind failed");
+        return Err(ApiError::Unknown(anyhow!(
+            "tailwind failed: {}",
+            stderr.trim()
+        )));
+    }
+
+    fs::read_to_string(dir.join("output.css"))
+        .await
+        .map_err(|e| {
+            error!(?e, "failed to read tailwind output");
+            ApiError::IoError(e)
+        })
+}
@@ -0,0 +1,23 @@
+let wasm_bindgen;
+(function() {
+    const __exports = {};
+    let script_src;
+    if (typeof document !== 'undefined' && document.currentScript !== null) {
+        script_src = new URL(document.currentScript.src, location.href).toString();
+    }
+    let wasm = undefined;
+
+    async function __wbg_init(module_or_path) {
+        if (wasm !== undefined) return wasm;
+
+        if (typeof module_or_path === 'undefined' && typeof script_src !== 'undefined') {
+            module_or_path = script_src.replace(/\.js$/, '_bg.wasm');
+        }
+        const { instance } = await WebAssembly.instantiateStreaming(fetch(module_or_path), {});
+        wasm = instance.exports;
+        return wasm;
+    }
+
+    wasm_bindgen = Object.assign(__wbg_init, { initSync }, __exports);
+
+})();
@@ -0,0 +1,54 @@
+use gloo_net::http::Request;
+use serde::Serialize;
+
+use crate::api::{BACKEND_URL, RequestError};
+use crate::utils::project::ProjectFiles;
+
+#[derive(Serialize)]
+struct ExportPayload<'a> {
+    version: &'a str,
+    title: &'a str,
+    format: &'a str,
+    tailwind: bool,
+    #[serde(flatten)]
+    files: &'a ProjectFiles,
+}
+
+/// Has the backend package the project as a Cargo project and saves the zip archive.
+pub async fn download(
+    files: &ProjectFiles,
+    version: &str,
+    title: &str,
+    tailwind: bool,
+) -> Result<(), RequestError> {
+    let payload = ExportPayload {
+        version,
+        title,
+        format: "zip",
+        tailwind,
+        files,
+    };
+    let resp = Request::post(&format!("{BACKEND_URL}/export"))
+        .json(&payload)
+        .map_err(RequestError::from_request)?
+        .send()
+        .await
+        .map_err(RequestError::from_request)?;
+    if !resp.ok() {
+        return Err(RequestError::from_response(&resp).await);
+    }
+    // the backend names the archive after the title
+    let file_name = resp
+        .headers()
+        .get("content-disposition")
+        .and_then(|it| {
+            it.split("filename=\"")
+                .nth(1)
+                .map(|it| it.trim_end_matches('"').to_string())
+        })
+        .unwrap_or_else(|| "yew-app.zip".to_string());
+    let bytes = resp.binary().await.map_err(RequestError::from_request)?;
+
+    crate::utils::save_file(&bytes, "application/zip", &file_name);
+    Ok(())
+}
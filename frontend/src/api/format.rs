@@ -0,0 +1,29 @@
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{BACKEND_URL, RequestError};
+
+#[derive(Serialize)]
+struct FormatPayload<'a> {
+    code: &'a str,
+}
+
+#[derive(Deserialize)]
+struct Formatted {
+    code: String,
+}
+
+/// Formats `code` with rustfmt on the backend.
+pub async fn format(code: &str) -> Result<String, RequestError> {
+    let resp = Request::post(&format!("{BACKEND_URL}/format"))
+        .json(&FormatPayload { code })
+        .map_err(RequestError::from_request)?
+        .send()
+        .await
+        .map_err(RequestError::from_request)?;
+    if !resp.ok() {
+        return Err(RequestError::from_response(&resp).await);
+    }
+    let formatted: Formatted = resp.json().await.map_err(RequestError::from_request)?;
+    Ok(formatted.code)
+}
@@ -0,0 +1,30 @@
+use gloo_net::http::Request;
+
+use crate::api::{BACKEND_URL, RequestError};
+use crate::utils::project::ProjectFiles;
+
+/// Where a project can be imported from.
+pub enum Source<'a> {
+    Gist(&'a str),
+    Url(&'a str),
+}
+
+/// Loads a project from a gist or a raw file, fetched by the backend.
+pub async fn import(source: Source<'_>) -> Result<ProjectFiles, RequestError> {
+    let (key, value) = match source {
+        Source::Gist(id) => ("gist", id),
+        Source::Url(url) => ("url", url),
+    };
+    let resp = Request::get(&format!("{BACKEND_URL}/import"))
+        .query([(key, value)])
+        .send()
+        .await
+        .map_err(RequestError::from_request)?;
+    if !resp.ok() {
+        return Err(RequestError::from_response(&resp).await);
+    }
+    resp.json().await.map_err(|e| RequestError::Server {
+        status: resp.status(),
+        message: format!("invalid response: {e}"),
+    })
+}
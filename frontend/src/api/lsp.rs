@@ -0,0 +1,194 @@
+//! A JSON-RPC client for the rust-analyzer sessions the backend serves on `/lsp`.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use futures::channel::{mpsc, oneshot};
+use futures::{SinkExt, StreamExt};
+use gloo_net::websocket::Message;
+use gloo_net::websocket::futures::WebSocket;
+use serde_json::{Value, json};
+use yew::Callback;
+
+use crate::api::BACKEND_URL;
+
+/// Where the backend puts the template crate, as far as the editor is concerned.
+pub const ROOT_URI: &str = "file:///workspace";
+/// The document the editor's code tab is kept in sync with.
+pub const MAIN_URI: &str = "file:///workspace/src/main.rs";
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum LspError {
+    #[error("the language server is unavailable")]
+    Unavailable,
+    #[error("the language server failed: {0}")]
+    Server(String),
+}
+
+type Pending = HashMap<u64, oneshot::Sender<Result<Value, LspError>>>;
+
+struct Inner {
+    outgoing: mpsc::UnboundedSender<String>,
+    pending: RefCell<Pending>,
+    next_id: Cell<u64>,
+}
+
+/// A connection to one rust-analyzer session. The session ends once every clone is dropped.
+#[derive(Clone)]
+pub struct LspClient(Rc<Inner>);
+
+impl LspClient {
+    /// Opens a session against the `version` template and initializes it. Diagnostics the
+    /// server publishes for [`MAIN_URI`] are reported to `on_diagnostics`.
+    pub async fn connect(
+        version: &str,
+        on_diagnostics: Callback<Vec<Value>>,
+    ) -> Result<Self, LspError> {
+        let url = format!("{}/lsp?version={version}", websocket_url(BACKEND_URL));
+        let socket = WebSocket::open(&url).map_err(|_| LspError::Unavailable)?;
+        let (mut sink, mut stream) = socket.split();
+
+        let (outgoing, mut receiver) = mpsc::unbounded::<String>();
+        wasm_bindgen_futures::spawn_local(async move {
+            while let Some(text) = receiver.next().await {
+                if sink.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            let _ = sink.close().await;
+        });
+
+        let inner = Rc::new(Inner {
+            outgoing,
+            pending: RefCell::default(),
+            next_id: Cell::new(1),
+        });
+        // the read loop only holds a weak reference, so dropping the client ends the session
+        let weak = Rc::downgrade(&inner);
+        wasm_bindgen_futures::spawn_local(async move {
+            while let Some(Ok(message)) = stream.next().await {
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let Some(inner) = weak.upgrade() else {
+                    break;
+                };
+                match serde_json::from_str::<Value>(&text) {
+                    Ok(message) => inner.receive(message, &on_diagnostics),
+                    Err(e) => tracing::warn!(?e, "unreadable message from the language server"),
+                }
+            }
+            // the session is over, fail whatever is still waiting on it
+            if let Some(inner) = weak.upgrade() {
+                for (_, sender) in inner.pending.borrow_mut().drain() {
+                    let _ = sender.send(Err(LspError::Unavailable));
+                }
+            }
+        });
+
+        let client = Self(inner);
+        client
+            .request(
+                "initialize",
+                json!({
+                    "processId": null,
+                    "rootUri": ROOT_URI,
+                    "capabilities": {
+                        "textDocument": {
+                            "synchronization": { "dynamicRegistration": false },
+                            "completion": {
+                                "completionItem": {
+                                    "snippetSupport": true,
+                                    "documentationFormat": ["markdown", "plaintext"],
+                                },
+                            },
+                            "hover": { "contentFormat": ["markdown", "plaintext"] },
+                            "definition": { "linkSupport": false },
+                            "publishDiagnostics": { "relatedInformation": false },
+                        },
+                    },
+                }),
+            )
+            .await?;
+        client.notify("initialized", json!({}));
+        Ok(client)
+    }
+
+    /// Sends a request and waits for its result.
+    pub async fn request(&self, method: &str, params: Value) -> Result<Value, LspError> {
+        let id = self.0.next_id.get();
+        self.0.next_id.set(id + 1);
+        let (sender, receiver) = oneshot::channel();
+        self.0.pending.borrow_mut().insert(id, sender);
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        receiver.await.unwrap_or(Err(LspError::Unavailable))
+    }
+
+    pub fn notify(&self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn send(&self, message: Value) {
+        // a closed channel means the session ended, which the read loop already reported
+        let _ = self.0.outgoing.unbounded_send(message.to_string());
+    }
+}
+
+impl Inner {
+    fn receive(&self, message: Value, on_diagnostics: &Callback<Vec<Value>>) {
+        let id = message.get("id").cloned();
+        match (message.get("method").and_then(Value::as_str), id) {
+            // a request from the server, such as registering capabilities or reporting
+            // progress. None of them need an answer beyond acknowledging it
+            (Some(_), Some(id)) => {
+                let reply = json!({ "jsonrpc": "2.0", "id": id, "result": null });
+                let _ = self.outgoing.unbounded_send(reply.to_string());
+            }
+            (Some("textDocument/publishDiagnostics"), None) => {
+                let params = &message["params"];
+                if params["uri"] == MAIN_URI {
+                    let diagnostics = params["diagnostics"].as_array().cloned();
+                    on_diagnostics.emit(diagnostics.unwrap_or_default());
+                }
+            }
+            (Some(_), None) => {}
+            (None, Some(id)) => {
+                let Some(sender) = id
+                    .as_u64()
+                    .and_then(|id| self.pending.borrow_mut().remove(&id))
+                else {
+                    return;
+                };
+                let result = match message.get("error") {
+                    Some(error) => Err(LspError::Server(
+                        error["message"]
+                            .as_str()
+                            .unwrap_or("unknown error")
+                            .to_string(),
+                    )),
+                    None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+                };
+                let _ = sender.send(result);
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+/// The websocket URL for the backend at `url`, which is either absolute or relative to the page.
+fn websocket_url(url: &str) -> String {
+    if let Some(rest) = url.strip_prefix("https://") {
+        return format!("wss://{rest}");
+    }
+    if let Some(rest) = url.strip_prefix("http://") {
+        return format!("ws://{rest}");
+    }
+    let location = gloo::utils::window().location();
+    let scheme = match location.protocol().as_deref() {
+        Ok("https:") => "wss",
+        _ => "ws",
+    };
+    let host = location.host().unwrap_or_default();
+    format!("{scheme}://{host}{url}")
+}
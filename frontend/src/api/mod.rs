@@ -1,5 +1,11 @@
+pub mod export;
+pub mod format;
+pub mod import;
+pub mod lsp;
 pub mod share;
 
+use gloo_net::http::Response;
+
 pub const BACKEND_URL: &str = match option_env!("BACKEND_URL") {
     Some(v) => v,
     None => {
@@ -12,3 +18,145 @@ pub const BACKEND_URL: &str = match option_env!("BACKEND_URL") {
         DEFAULT
     }
 };
+
+/// Template a project is compiled with, mirroring the backend's `YewVersion`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum YewVersion {
+    #[default]
+    Stable,
+    Next,
+}
+
+impl YewVersion {
+    pub fn name(self) -> &'static str {
+        match self {
+            YewVersion::Stable => "stable",
+            YewVersion::Next => "next",
+        }
+    }
+
+    /// The version called `name`, the stable one for unknown names.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "next" => YewVersion::Next,
+            _ => YewVersion::Stable,
+        }
+    }
+}
+
+/// How much effort goes into making the output small and fast, mirroring the backend's
+/// `OptLevel`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptLevel {
+    Debug,
+    #[default]
+    Release,
+    Size,
+}
+
+impl OptLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            OptLevel::Debug => "debug",
+            OptLevel::Release => "release",
+            OptLevel::Size => "size",
+        }
+    }
+
+    /// The level called `name`, the release level for unknown names.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "debug" => OptLevel::Debug,
+            "size" => OptLevel::Size,
+            _ => OptLevel::Release,
+        }
+    }
+}
+
+/// Category of an error returned by the backend, mirroring `kind` in its JSON error body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorKind {
+    CompileFailed,
+    Timeout,
+    QueueFull,
+    InvalidInput,
+    NotFound,
+    TooLarge,
+    Forbidden,
+    RateLimited,
+    Internal,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The JSON body the backend sends with every error response.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct ApiErrorBody {
+    pub kind: ApiErrorKind,
+    pub message: String,
+    #[serde(default)]
+    pub details: Option<serde_json::Value>,
+}
+
+/// A failed request to the backend for anything but compiling, e.g. sharing or formatting.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RequestError {
+    #[error("nothing was found at this link, it may be mistyped")]
+    NotFound,
+    #[error("the project is too large: {0}")]
+    TooLarge(String),
+    /// The backend or the services it relies on are limiting requests.
+    #[error("the playground is handling too many requests, try again in a moment: {0}")]
+    RateLimited(String),
+    #[error("you are offline, this needs a connection to the playground")]
+    Offline,
+    #[error("could not reach the backend: {0}")]
+    Network(String),
+    #[error("the backend failed with status {status}: {message}")]
+    Server { status: u16, message: String },
+    #[error("the link is damaged and the project cannot be read: {0}")]
+    Corrupt(String),
+    /// The backend refused the request, e.g. code that does not parse.
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl RequestError {
+    /// Whether trying again can help, i.e. the failure was not about the project itself.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited(_) | Self::Offline | Self::Network(_) | Self::Server { .. }
+        )
+    }
+
+    pub(crate) fn from_request(e: gloo_net::Error) -> Self {
+        let online = web_sys::window().is_none_or(|it| it.navigator().on_line());
+        if online {
+            Self::Network(e.to_string())
+        } else {
+            Self::Offline
+        }
+    }
+
+    pub(crate) async fn from_response(resp: &Response) -> Self {
+        let status = resp.status();
+        // proxies in front of the backend answer with plain text, not our JSON body
+        let (kind, message) = match resp.json::<ApiErrorBody>().await {
+            Ok(body) => (body.kind, body.message),
+            Err(_) => (ApiErrorKind::Unknown, resp.status_text()),
+        };
+        match (kind, status) {
+            (ApiErrorKind::NotFound, _) | (_, 404) => Self::NotFound,
+            (ApiErrorKind::TooLarge, _) | (_, 413) => Self::TooLarge(message),
+            (ApiErrorKind::QueueFull | ApiErrorKind::RateLimited, _) | (_, 429) => {
+                Self::RateLimited(message)
+            }
+            (ApiErrorKind::InvalidInput, _) => Self::Invalid(message),
+            _ => Self::Server { status, message },
+        }
+    }
+}
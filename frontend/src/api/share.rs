@@ -1,118 +1,79 @@
-use anyhow::{Context, Result, anyhow};
-use gloo_net::http::{Request, Response};
-use serde::{Deserialize, Serialize};
-use serde_json::{Value, json};
-use std::collections::HashMap;
-use std::fmt;
-
-#[cfg(feature = "emulator")]
-const FIRESTORE_URL: &str =
-    "http://localhost:8080/v1/projects/pastify-app/databases/(default)/documents/pastes";
-
-#[cfg(not(feature = "emulator"))]
-const FIRESTORE_URL: &str =
-    "https://firestore.googleapis.com/v1/projects/pastify-app/databases/(default)/documents/pastes";
-
-#[derive(Debug, thiserror::Error, Deserialize)]
-#[error("{message}")]
-pub struct FirestoreError {
-    message: String,
-}
-
-impl FirestoreError {
-    async fn anyhow_from_response<V>(resp: &Response) -> Result<V> {
-        let mut error = resp.json::<Value>().await?;
-        let error = error
-            .get_mut(ERROR)
-            .ok_or_else(|| anyhow!("invalid schema returned by firestore"))?;
-        let error = error.take();
-        let error = serde_json::from_value::<FirestoreError>(error)
-            .context("invalid schema for error field returned by firestore")?;
-        Err(error.into())
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-pub struct PasteFields {
-    content: HashMap<String, String>,
-    #[serde(rename = "createdBy")]
-    created_by: HashMap<String, Option<String>>,
-}
+use std::collections::BTreeMap;
 
-const STRING_VALUE: &str = "stringValue";
-const NULL_VALUE: &str = "nullValue";
-const ERROR: &str = "error";
-
-impl PasteFields {
-    pub fn content(&self) -> &str {
-        self.content.get(STRING_VALUE).unwrap().as_str()
-    }
-
-    pub fn into_content(mut self) -> String {
-        self.content.remove(STRING_VALUE).unwrap()
-    }
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
 
-    pub fn created_by(&self) -> Option<&str> {
-        self.created_by
-            .get(STRING_VALUE)
-            .map(|it| it.as_ref().unwrap().as_str())
-    }
+use crate::api::{BACKEND_URL, OptLevel, RequestError, YewVersion};
+use crate::utils::project::ProjectFiles;
+
+/// A shared project with everything needed to open it the way it was shared.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Share {
+    pub version: YewVersion,
+    pub title: String,
+    pub files: ProjectFiles,
+    pub settings: ShareSettings,
+    pub dependencies: BTreeMap<String, String>,
+    /// Set by the backend, unknown for shares made before it was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// The share this one was forked from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
 }
 
-impl fmt::Debug for PasteFields {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.debug_struct("Fields")
-            .field("content", &self.content())
-            .field("created_by", &format!("{:?}", self.created_by()))
-            .finish()
+impl Share {
+    /// Whether `other` holds the same project, whenever and from whichever share it was made.
+    /// The dependencies follow from the version, so they are left out too.
+    pub fn same_project(&self, other: &Share) -> bool {
+        self.version == other.version
+            && self.title == other.title
+            && self.files == other.files
+            && self.settings == other.settings
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PasteDocument {
-    #[serde(skip_serializing)]
-    name: String,
-    pub fields: PasteFields,
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShareSettings {
+    pub opt: OptLevel,
+    pub tailwind: bool,
 }
 
-impl PasteDocument {
-    pub fn id(&self) -> String {
-        self.name.replace(
-            "projects/pastify-app/databases/(default)/documents/pastes/",
-            "",
-        )
-    }
+#[derive(Deserialize)]
+struct CreatedShare {
+    id: String,
 }
 
-pub async fn get(id: &str) -> Result<PasteDocument> {
-    let resp = Request::get(&format!("{}/{}", FIRESTORE_URL, id))
+pub async fn get(id: &str) -> Result<Share, RequestError> {
+    let resp = Request::get(&format!("{BACKEND_URL}/share/{id}"))
         .send()
-        .await?;
-    if resp.status() == 200 {
-        Ok(resp.json().await?)
-    } else {
-        FirestoreError::anyhow_from_response(&resp).await
+        .await
+        .map_err(RequestError::from_request)?;
+    if !resp.ok() {
+        return Err(RequestError::from_response(&resp).await);
     }
+    resp.json().await.map_err(|e| RequestError::Server {
+        status: resp.status(),
+        message: format!("invalid response: {e}"),
+    })
 }
 
-pub async fn create(content: &str) -> Result<PasteDocument> {
-    let doc = json!({
-        "fields": {
-            "content": {
-                STRING_VALUE: content
-            },
-            "createdBy": {
-                NULL_VALUE: Option::<String>::None
-            }
-        }
-    });
-
-    let resp = Request::post(FIRESTORE_URL).json(&doc)?.send().await?;
-
-    if (200..300).contains(&resp.status()) {
-        let paste = resp.json::<PasteDocument>().await?;
-        Ok(paste)
-    } else {
-        FirestoreError::anyhow_from_response(&resp).await
+/// Stores `share` on the backend and returns the id of the new share.
+pub async fn create(share: &Share) -> Result<String, RequestError> {
+    let resp = Request::post(&format!("{BACKEND_URL}/share"))
+        .json(share)
+        .map_err(RequestError::from_request)?
+        .send()
+        .await
+        .map_err(RequestError::from_request)?;
+    if !resp.ok() {
+        return Err(RequestError::from_response(&resp).await);
     }
+    let CreatedShare { id } = resp.json().await.map_err(|e| RequestError::Server {
+        status: resp.status(),
+        message: format!("invalid response: {e}"),
+    })?;
+    Ok(id)
 }
@@ -1,19 +1,79 @@
-use crate::components::crates::CratesPanel;
-use crate::components::editor::Editor;
-use crate::components::output::{CompileTimer, OutputContainer};
+use crate::api::share::{Share, ShareSettings};
+use crate::api::{OptLevel, RequestError, YewVersion};
+use crate::components::crates::{CratesPanel, template_deps};
+use crate::components::drafts::DraftsMenu;
+use crate::components::editor::{Editor, SetFiles};
+use crate::components::opt_level::OptLevelPicker;
+use crate::components::output::{CompileTimer, OutputContainer, RunSettings};
+use crate::components::share_dialog::ShareDialog;
+use crate::components::shortcuts_help::ShortcutsHelp;
 use crate::components::snippets::SnippetPicker;
-use crate::utils::query::Query;
+use crate::components::toast::Toast;
+use crate::components::workspaces::WorkspacesSidebar;
+use crate::utils::drafts::{self, Draft};
+use crate::utils::project::{ProjectFile, ProjectFiles};
+use crate::utils::query::use_fragment;
+use crate::utils::share::use_share;
+use crate::utils::shortcuts::{ShortcutActions, use_shortcuts};
+use crate::utils::workspaces::{self, Workspace};
 use crate::{ActionButtonState, ActionButtonStateContext, icon};
-use gloo::history::{BrowserHistory, History};
+use gloo::timers::callback::Timeout;
 use split_yew::{Direction, Split};
 use std::rc::Rc;
 use yew::prelude::*;
 use yew::suspense::Suspense;
 
+/// How long typing has to pause before the draft is saved.
+const AUTOSAVE_DELAY_MS: u32 = 1000;
+
+/// A failed request, e.g. to share or download the project, shown until it is dismissed or
+/// retried.
+#[derive(Debug, Clone, PartialEq)]
+enum RequestFailure {
+    Create(RequestError),
+    Load(RequestError),
+    Export(RequestError),
+    Format(RequestError),
+}
+
+impl RequestFailure {
+    fn title(&self) -> &'static str {
+        match (self, self.error()) {
+            (_, RequestError::Offline) => "You are offline",
+            (_, RequestError::RateLimited(_)) => "The playground is busy",
+            (RequestFailure::Create(_), RequestError::TooLarge(_)) => {
+                "The project is too large to share"
+            }
+            (RequestFailure::Load(_), RequestError::NotFound) => "Project not found",
+            (RequestFailure::Create(_), _) => "Could not share the project",
+            (RequestFailure::Load(_), _) => "Could not load the project",
+            (RequestFailure::Export(_), _) => "Could not download the project",
+            (RequestFailure::Format(_), _) => "Could not format the code",
+        }
+    }
+
+    fn error(&self) -> &RequestError {
+        match self {
+            RequestFailure::Create(e)
+            | RequestFailure::Load(e)
+            | RequestFailure::Export(e)
+            | RequestFailure::Format(e) => e,
+        }
+    }
+}
+
+/// The title is the name of the workspace it is saved as.
+fn workspace_name(title: &str) -> String {
+    match title.trim() {
+        "" => "Untitled".to_string(),
+        title => title.to_string(),
+    }
+}
+
 #[component]
 pub fn App() -> Html {
-    let editor_contents = use_mut_ref(String::new);
-    let data = use_state(|| None::<(Rc<str>, AttrValue)>);
+    let editor_contents = use_mut_ref(ProjectFiles::default);
+    let data = use_state(|| None::<(Rc<ProjectFiles>, RunSettings)>);
     let run_count = use_state(|| 0u32);
     let query = crate::utils::query::use_query();
     let initial_version = query
@@ -21,15 +81,47 @@ pub fn App() -> Html {
         .and_then(|q| q.version.as_deref())
         .unwrap_or("stable");
     let version = use_state(|| AttrValue::from(initial_version));
+    let opt_level = use_state(|| AttrValue::from("release"));
+    let tailwind = use_state(|| false);
+    let title = use_state(String::new);
+    let request_failure = use_state(|| None::<RequestFailure>);
+    let sharing = {
+        let request_failure = request_failure.clone();
+        use_share(Callback::from(move |e: RequestError| {
+            request_failure.set(Some(RequestFailure::Create(e)))
+        }))
+    };
+    let load_attempt = use_state(|| 0u32);
 
     let snippet_code = use_state(|| None::<AttrValue>);
+    let set_files = use_state(|| None::<SetFiles>);
+    let sidebar_open = use_state(|| false);
+    // the workspace being edited, saved together with the draft
+    let workspace = use_state(|| None::<AttrValue>);
+
+    // drafts of this page load replace each other, earlier ones are kept for restoring
+    let session = *use_memo((), |_| js_sys::Date::now());
+    // the contents the editor was opened with, unchanged contents are not worth a draft
+    let baseline = use_mut_ref(|| None::<ProjectFiles>);
+    let autosave = use_mut_ref(|| None::<Timeout>);
+    let fragment = use_fragment();
+    let restorable = use_state(|| {
+        let opened_project = query.as_ref().is_some_and(|q| {
+            q.shared.is_some() || q.code.is_some() || q.gist.is_some() || q.url.is_some()
+        }) || !fragment.is_empty();
+        if opened_project {
+            None
+        } else {
+            drafts::load(initial_version).into_iter().next()
+        }
+    });
 
     let action_button_state = use_context::<ActionButtonStateContext>().unwrap();
 
     let split_sizes = use_state(|| vec![100.0, 0.0]);
     let output_collapsed = use_state(|| true);
 
-    let on_run_click = {
+    let run = {
         let action_button_state = action_button_state.clone();
         let editor_contents = editor_contents.clone();
         let split_sizes = split_sizes.clone();
@@ -37,10 +129,16 @@ pub fn App() -> Html {
         let data = data.clone();
         let run_count = run_count.clone();
         let version = version.clone();
-        move |_| {
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        Callback::from(move |_: ()| {
             data.set(Some((
-                Rc::from(editor_contents.as_ref().borrow().as_str()),
-                (*version).clone(),
+                Rc::new(editor_contents.as_ref().borrow().clone()),
+                RunSettings {
+                    version: (*version).clone(),
+                    opt_level: (*opt_level).clone(),
+                    tailwind: *tailwind,
+                },
             )));
             run_count.set(*run_count + 1);
             if *output_collapsed {
@@ -48,52 +146,283 @@ pub fn App() -> Html {
                 split_sizes.set(vec![50.0, 50.0]);
             }
             action_button_state.dispatch(ActionButtonState::Disabled);
-        }
+        })
+    };
+
+    let on_retry = {
+        let action_button_state = action_button_state.clone();
+        let run_count = run_count.clone();
+        Callback::from(move |_| {
+            run_count.set(*run_count + 1);
+            action_button_state.dispatch(ActionButtonState::Disabled);
+        })
     };
 
-    let on_share_click = {
+    let share_project = {
+        let editor_contents = editor_contents.clone();
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        let title = title.clone();
+        let request_failure = request_failure.clone();
+        let share = sharing.share.clone();
+        Callback::from(move |_: ()| {
+            request_failure.set(None);
+            share.emit(Share {
+                version: YewVersion::from_name(&version),
+                title: (*title).clone(),
+                files: editor_contents.as_ref().borrow().clone(),
+                settings: ShareSettings {
+                    opt: OptLevel::from_name(&opt_level),
+                    tailwind: *tailwind,
+                },
+                dependencies: template_deps(&version),
+                created_at: None,
+                parent: None,
+            })
+        })
+    };
+
+    let download_project = {
         let editor_contents = editor_contents.clone();
         let action_button_state = action_button_state.clone();
         let version = version.clone();
-        move |_| {
+        let title = title.clone();
+        let tailwind = tailwind.clone();
+        let request_failure = request_failure.clone();
+        Callback::from(move |_: ()| {
             let action_button_state = action_button_state.clone();
-            let editor_contents = editor_contents.clone();
-            let version = version.clone();
-
-            // https://tenor.com/view/this-is-fine-fire-house-burning-okay-gif-5263684
-            // this is fine because no one can do anuthing with the refcell while we holding it
-            // alternate is clone, which is expensive
-            #[allow(clippy::await_holding_refcell_ref)]
+            let request_failure = request_failure.clone();
+            let files = editor_contents.as_ref().borrow().clone();
+            let version = version.to_string();
+            let title = (*title).clone();
+            let tailwind = *tailwind;
+            request_failure.set(None);
             yew::platform::spawn_local(async move {
                 action_button_state.dispatch(ActionButtonState::Disabled);
-                let history = BrowserHistory::new();
-
-                let content = editor_contents.as_ref().borrow();
-                let content = content.as_str();
-                let paste = crate::api::share::create(content).await.expect("fucked up");
-                let id = paste.id();
-                let ver = if *version == "stable" {
-                    None
-                } else {
-                    Some((*version).to_string())
-                };
-                let query = Query {
-                    shared: Some(id),
-                    code: None,
-                    version: ver,
-                };
-                history
-                    .push_with_query("/", query)
-                    .expect("failed to navigate");
+                if let Err(e) =
+                    crate::api::export::download(&files, &version, &title, tailwind).await
+                {
+                    request_failure.set(Some(RequestFailure::Export(e)));
+                }
                 action_button_state.dispatch(ActionButtonState::Enabled);
             })
+        })
+    };
+
+    let format_code = {
+        let editor_contents = editor_contents.clone();
+        let set_files = set_files.clone();
+        let request_failure = request_failure.clone();
+        Callback::from(move |_: ()| {
+            let files = editor_contents.as_ref().borrow().clone();
+            let set_files = set_files.clone();
+            let request_failure = request_failure.clone();
+            request_failure.set(None);
+            yew::platform::spawn_local(async move {
+                match crate::api::format::format(&files.code).await {
+                    Ok(code) => {
+                        set_files.set(Some(SetFiles(Rc::new(ProjectFiles { code, ..files }))))
+                    }
+                    Err(e) => request_failure.set(Some(RequestFailure::Format(e))),
+                }
+            })
+        })
+    };
+
+    let onerror = {
+        let request_failure = request_failure.clone();
+        move |e: RequestError| request_failure.set(Some(RequestFailure::Load(e)))
+    };
+
+    let toast = request_failure.as_ref().map(|failure| {
+        let on_dismiss = {
+            let request_failure = request_failure.clone();
+            Callback::from(move |_| request_failure.set(None))
+        };
+        let on_retry = failure.error().is_retryable().then(|| match failure {
+            RequestFailure::Create(_) => share_project.clone(),
+            RequestFailure::Export(_) => download_project.clone(),
+            RequestFailure::Format(_) => format_code.clone(),
+            RequestFailure::Load(_) => {
+                let request_failure = request_failure.clone();
+                let load_attempt = load_attempt.clone();
+                Callback::from(move |_| {
+                    request_failure.set(None);
+                    load_attempt.set(*load_attempt + 1);
+                })
+            }
+        });
+        html! {
+            <Toast
+                title={failure.title()}
+                message={failure.error().to_string()}
+                {on_retry}
+                {on_dismiss}
+            />
+        }
+    });
+
+    let onload = {
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        let title = title.clone();
+        let opened = sharing.opened.clone();
+        let shared = query.as_ref().and_then(|q| q.shared.clone());
+        // links to shares made before they stored their version still carry it in the query
+        let query_version = query.as_ref().and_then(|q| q.version.clone());
+        move |mut share: Share| {
+            if let Some(version) = &query_version {
+                share.version = YewVersion::from_name(version);
+            }
+            version.set(AttrValue::from(share.version.name()));
+            opt_level.set(AttrValue::from(share.settings.opt.name()));
+            tailwind.set(share.settings.tailwind);
+            title.set(share.title.clone());
+            opened.emit((shared.clone().map(AttrValue::from), share));
+        }
+    };
+
+    // the editor's project as a workspace, with a new id unless `id` is given
+    let current_workspace = {
+        let editor_contents = editor_contents.clone();
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        move |name: String, id: Option<&str>| {
+            let mut workspace = Workspace::new(
+                name,
+                version.to_string(),
+                editor_contents.as_ref().borrow().clone(),
+                ShareSettings {
+                    opt: OptLevel::from_name(&opt_level),
+                    tailwind: *tailwind,
+                },
+            );
+            if let Some(id) = id {
+                workspace.id = id.to_string();
+            }
+            workspace
+        }
+    };
+
+    let save_workspace = {
+        let current_workspace = current_workspace.clone();
+        let workspace = workspace.clone();
+        let title = title.clone();
+        move || {
+            let Some(id) = &*workspace else {
+                return;
+            };
+            let saved = current_workspace(workspace_name(&title), Some(id.as_str()));
+            yew::platform::spawn_local(async move {
+                if let Err(e) = workspaces::save(&[saved]).await {
+                    tracing::warn!(%e, "failed to save workspace");
+                }
+            });
         }
     };
 
-    let oninput = {
-        move |v| {
-            *editor_contents.as_ref().borrow_mut() = v;
+    // the editor keeps the `oninput` it was first given, so its autosave calls this render's
+    let latest_save_workspace = use_mut_ref(Callback::noop);
+    *latest_save_workspace.borrow_mut() = {
+        let save_workspace = save_workspace.clone();
+        Callback::from(move |_: ()| save_workspace())
+    };
+
+    let open_workspace = {
+        let save_workspace = save_workspace.clone();
+        let workspace = workspace.clone();
+        let set_files = set_files.clone();
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        let title = title.clone();
+        let forget_share = sharing.forget.clone();
+        Callback::from(move |opened: Workspace| {
+            if workspace.as_deref() != Some(opened.id.as_str()) {
+                save_workspace();
+            }
+            version.set(AttrValue::from(opened.version));
+            opt_level.set(AttrValue::from(opened.settings.opt.name()));
+            tailwind.set(opened.settings.tailwind);
+            title.set(opened.name);
+            forget_share.emit(());
+            workspace.set(Some(AttrValue::from(opened.id)));
+            set_files.set(Some(SetFiles(Rc::new(opened.files))));
+        })
+    };
+
+    let shortcuts = use_shortcuts(ShortcutActions {
+        run: run.clone(),
+        // the share button is disabled while a request is running
+        save: {
+            let action_button_state = action_button_state.clone();
+            let save_workspace = save_workspace.clone();
+            let workspace = workspace.clone();
+            let share_project = share_project.clone();
+            Callback::from(move |_| {
+                if workspace.is_some() {
+                    save_workspace();
+                } else if !action_button_state.disabled() {
+                    share_project.emit(());
+                }
+            })
+        },
+        format: format_code.clone(),
+        toggle_version: {
+            let version = version.clone();
+            Callback::from(move |_| {
+                version.set(AttrValue::from(if *version == "next" {
+                    "stable"
+                } else {
+                    "next"
+                }))
+            })
+        },
+    });
+    let help_open = shortcuts.help_open.clone();
+
+    let restore_draft = {
+        let set_files = set_files.clone();
+        let restorable = restorable.clone();
+        Callback::from(move |draft: Draft| {
+            restorable.set(None);
+            set_files.set(Some(SetFiles(Rc::new(draft.files))));
+        })
+    };
+
+    let onready = {
+        let editor_contents = editor_contents.clone();
+        let baseline = baseline.clone();
+        move |_| *baseline.borrow_mut() = Some(editor_contents.as_ref().borrow().clone())
+    };
+
+    // drafts are kept per version, the one selected when the draft is saved
+    let latest_version = use_mut_ref(AttrValue::default);
+    *latest_version.borrow_mut() = (*version).clone();
+
+    let oninput = move |(file, v): (ProjectFile, String)| {
+        *editor_contents.as_ref().borrow_mut().get_mut(file) = v;
+
+        // the editor emits its initial contents before it is ready
+        match &*baseline.borrow() {
+            Some(baseline) if *baseline != *editor_contents.as_ref().borrow() => {}
+            _ => return,
         }
+        let editor_contents = editor_contents.clone();
+        let version = latest_version.clone();
+        let save_workspace = latest_save_workspace.clone();
+        // replacing the timeout cancels the pending save
+        *autosave.borrow_mut() = Some(Timeout::new(AUTOSAVE_DELAY_MS, move || {
+            drafts::save(
+                &version.borrow(),
+                session,
+                &editor_contents.as_ref().borrow(),
+            );
+            save_workspace.borrow().emit(());
+        }));
     };
 
     let mut classes = Classes::from(
@@ -110,7 +439,20 @@ pub fn App() -> Html {
         <div class="flex flex-col h-screen">
             <header class="bg-gray-700 p-3 flex justify-between">
                 <div class="flex items-center gap-3">
-                    <button onclick={on_run_click} disabled={action_button_state.disabled()} class={&classes}>
+                    <button
+                        onclick={{
+                            let sidebar_open = sidebar_open.clone();
+                            move |_| sidebar_open.set(!*sidebar_open)
+                        }}
+                        title="Projects saved in this browser"
+                        class={classes!(
+                            "p-3", "cursor-pointer", "rounded-md", "shadow-lg",
+                            if *sidebar_open { "bg-gray-900" } else { "bg-gray-800 hover:bg-gray-900" }
+                        )}
+                    >
+                        {icon!("folder", classes!("fill-gray-200", "w-4", "h-4"))}
+                    </button>
+                    <button onclick={run.reform(|_: MouseEvent| ())} disabled={action_button_state.disabled()} class={&classes}>
                         if action_button_state.disabled() {
                             <span class="animate-spin inline-block w-4 h-4 border-2 border-gray-200 border-t-transparent rounded-full"></span>
                             {"Running..."}
@@ -119,6 +461,25 @@ pub fn App() -> Html {
                             {"Run"}
                         }
                     </button>
+                    <input
+                        type="text"
+                        placeholder="Untitled"
+                        maxlength="200"
+                        value={(*title).clone()}
+                        oninput={{
+                            let title = title.clone();
+                            move |e: InputEvent| {
+                                let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+                                title.set(input.value());
+                            }
+                        }}
+                        class="p-3 text-sm bg-gray-800 rounded-md shadow-lg text-gray-200 placeholder-gray-500 w-48"
+                    />
+                    if let Some(parent) = &sharing.forked_from {
+                        <a href={format!("/?shared={parent}")} class="text-xs text-gray-400 hover:text-gray-200 underline">
+                            {format!("forked from {parent}")}
+                        </a>
+                    }
                     <SnippetPicker
                         version={&*version}
                         on_select={{
@@ -128,6 +489,26 @@ pub fn App() -> Html {
                             }
                         }}
                     />
+                    <OptLevelPicker
+                        value={&*opt_level}
+                        on_select={{
+                            let opt_level = opt_level.clone();
+                            move |value: &'static str| opt_level.set(AttrValue::from(value))
+                        }}
+                    />
+                    <button
+                        onclick={{
+                            let tailwind = tailwind.clone();
+                            move |_| tailwind.set(!*tailwind)
+                        }}
+                        title="Generate Tailwind CSS for the classes used in the app"
+                        class={classes!(
+                            "p-3", "text-sm", "cursor-pointer", "rounded-md", "shadow-lg",
+                            if *tailwind { "bg-gray-900 text-gray-200" } else { "bg-gray-800 text-gray-500 hover:bg-gray-700" }
+                        )}
+                    >
+                        {"Tailwind"}
+                    </button>
                 </div>
 
                 <div class="flex items-center gap-3">
@@ -148,23 +529,110 @@ pub fn App() -> Html {
                         )}>{"Yew Next"}</button>
                     </div>
                     <CratesPanel version={&*version} />
-                    <button onclick={on_share_click} disabled={action_button_state.disabled()} class={classes}>{icon!("share", classes!("fill-gray-200", "w-4", "h-4"))} {"Share"}</button>
+                    <button
+                        onclick={{
+                            let help_open = help_open.clone();
+                            move |_| help_open.set(true)
+                        }}
+                        title="Keyboard shortcuts (?)"
+                        class="p-3 cursor-pointer bg-gray-800 rounded-md shadow-lg hover:bg-gray-900"
+                    >
+                        {icon!("keyboard", classes!("fill-gray-200", "w-4", "h-4"))}
+                    </button>
+                    <DraftsMenu version={&*version} on_select={restore_draft.clone()} />
+                    <button
+                        onclick={download_project.reform(|_: MouseEvent| ())}
+                        disabled={action_button_state.disabled()}
+                        title="Download as a Cargo project that builds with trunk"
+                        class={&classes}
+                    >
+                        {icon!("download", classes!("fill-gray-200", "w-4", "h-4"))}
+                        {"Download"}
+                    </button>
+                    <button onclick={share_project.reform(|_: MouseEvent| ())} disabled={action_button_state.disabled()} class={classes}>{icon!("share", classes!("fill-gray-200", "w-4", "h-4"))} {"Share"}</button>
                 </div>
             </header>
-            <Split min_sizes={vec![100.0, 0.0]} sizes={(*split_sizes).clone()} collapsed={collapsed_split} direction={Direction::Vertical} class="flex flex-col flex-grow overflow-hidden">
-                <div>
-                    <Suspense fallback={{html! {"loading..."}}}>
-                        <Editor {oninput} snippet_code={(*snippet_code).clone()} />
-                    </Suspense>
+            if let Some(draft) = &*restorable {
+                <div class="bg-gray-800 text-gray-200 text-sm px-3 py-2 flex items-center gap-3">
+                    <span>{format!("You have unsaved work from {}.", draft.saved_at_label())}</span>
+                    <button
+                        onclick={{
+                            let draft = draft.clone();
+                            restore_draft.reform(move |_: MouseEvent| draft.clone())
+                        }}
+                        class="px-2 py-1 bg-gray-700 hover:bg-gray-900 rounded-md cursor-pointer"
+                    >
+                        {"Restore"}
+                    </button>
+                    <button
+                        onclick={{
+                            let restorable = restorable.clone();
+                            move |_: MouseEvent| restorable.set(None)
+                        }}
+                        class="text-gray-400 hover:text-gray-200 cursor-pointer"
+                    >
+                        {"Dismiss"}
+                    </button>
                 </div>
-                <div class="w-full min-h-0">
-                    if let Some((ref code, ref ver)) = *data {
-                        <Suspense fallback={html! { <CompileTimer /> }}>
-                            <OutputContainer value={code} version={ver} key={*run_count} />
+            }
+            <div class="flex flex-grow overflow-hidden">
+                if *sidebar_open {
+                    <WorkspacesSidebar
+                        current={(*workspace).clone()}
+                        version={&*version}
+                        snapshot={{
+                            let current_workspace = current_workspace.clone();
+                            Callback::from(move |name: String| current_workspace(name, None))
+                        }}
+                        on_open={open_workspace}
+                        on_rename={{
+                            let workspace = workspace.clone();
+                            let title = title.clone();
+                            move |renamed: Workspace| {
+                                if workspace.as_deref() == Some(renamed.id.as_str()) {
+                                    title.set(renamed.name);
+                                }
+                            }
+                        }}
+                        on_delete={{
+                            let workspace = workspace.clone();
+                            move |id: AttrValue| {
+                                if workspace.as_ref() == Some(&id) {
+                                    workspace.set(None);
+                                }
+                            }
+                        }}
+                    />
+                }
+                <Split min_sizes={vec![100.0, 0.0]} sizes={(*split_sizes).clone()} collapsed={collapsed_split} direction={Direction::Vertical} class="flex flex-col flex-grow overflow-hidden">
+                    <div>
+                        <Suspense fallback={{html! {"loading..."}}}>
+                            <Editor {oninput} {onload} {onerror} {onready} on_shortcut={shortcuts.on_shortcut.clone()} version={(*version).clone()} attempt={*load_attempt} snippet_code={(*snippet_code).clone()} set_files={(*set_files).clone()} />
                         </Suspense>
-                    }
-                </div>
-            </Split>
+                    </div>
+                    <div class="w-full min-h-0">
+                        if let Some((ref files, ref settings)) = *data {
+                            <Suspense fallback={html! { <CompileTimer /> }}>
+                                <OutputContainer {files} settings={settings.clone()} {on_retry} key={*run_count} />
+                            </Suspense>
+                        }
+                    </div>
+                </Split>
+            </div>
+            if let Some((path, inline_path)) = &sharing.link {
+                <ShareDialog
+                    path={path.clone()}
+                    inline_path={inline_path.clone()}
+                    on_close={sharing.close.clone()}
+                />
+            }
+            if *help_open {
+                <ShortcutsHelp on_close={{
+                    let help_open = help_open.clone();
+                    move |_| help_open.set(false)
+                }} />
+            }
+            {toast}
         </div>
     }
 }
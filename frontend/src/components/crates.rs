@@ -8,6 +8,15 @@ fn parse_deps(json: &str) -> DepMap {
     serde_json::from_str(json).unwrap_or_default()
 }
 
+/// Dependencies of the template for `version`, crate name to version requirement.
+pub fn template_deps(version: &str) -> DepMap {
+    if version == "next" {
+        parse_deps(env!("APP_DEPS_NEXT"))
+    } else {
+        parse_deps(env!("APP_DEPS_STABLE"))
+    }
+}
+
 #[derive(Properties, PartialEq)]
 pub struct CratesPanelProps {
     pub version: AttrValue,
@@ -17,11 +26,7 @@ pub struct CratesPanelProps {
 pub fn CratesPanel(props: &CratesPanelProps) -> Html {
     let open = use_state(|| false);
 
-    let deps = if props.version == "next" {
-        parse_deps(env!("APP_DEPS_NEXT"))
-    } else {
-        parse_deps(env!("APP_DEPS_STABLE"))
-    };
+    let deps = template_deps(&props.version);
 
     html! {
         <div class="relative">
@@ -0,0 +1,75 @@
+use yew::prelude::*;
+
+use crate::utils::drafts::{self, Draft};
+
+#[derive(Properties, PartialEq)]
+pub struct DraftsMenuProps {
+    pub version: AttrValue,
+    pub on_select: Callback<Draft>,
+}
+
+/// The recently autosaved drafts for the current template, to go back to one of them.
+#[component]
+pub fn DraftsMenu(props: &DraftsMenuProps) -> Html {
+    let open = use_state(|| false);
+
+    // read when opened, the list changes with every autosave
+    let drafts = if *open {
+        drafts::load(&props.version)
+    } else {
+        Vec::new()
+    };
+
+    html! {
+        <div class="relative">
+            <button
+                onclick={{
+                    let open = open.clone();
+                    move |_: MouseEvent| open.set(!*open)
+                }}
+                class="p-3 text-sm cursor-pointer bg-gray-800 rounded-md shadow-lg text-gray-400 hover:bg-gray-900 flex items-center gap-1"
+            >
+                {"Drafts"}
+                <span class="text-xs">{if *open { "▲" } else { "▼" }}</span>
+            </button>
+            if *open {
+                <div class="absolute right-0 top-full mt-1 z-50 bg-gray-800 border border-gray-600 rounded-md shadow-xl p-3 w-max">
+                    <div class="text-gray-400 text-xs font-semibold mb-2 uppercase tracking-wider">{"Recent Drafts"}</div>
+                    if drafts.is_empty() {
+                        <div class="text-sm text-gray-500">{"No drafts saved yet"}</div>
+                    }
+                    <div class="flex flex-col gap-1">
+                        for draft in drafts {
+                            <button
+                                onclick={{
+                                    let open = open.clone();
+                                    let on_select = props.on_select.clone();
+                                    let draft = draft.clone();
+                                    move |_: MouseEvent| {
+                                        open.set(false);
+                                        on_select.emit(draft.clone());
+                                    }
+                                }}
+                                class="flex justify-between gap-4 text-sm whitespace-nowrap text-left cursor-pointer hover:bg-gray-700 rounded px-1"
+                            >
+                                <span class="text-gray-200">{draft.saved_at_label()}</span>
+                                <span class="text-gray-500 font-mono truncate max-w-64">{preview(&draft)}</span>
+                            </button>
+                        }
+                    </div>
+                </div>
+            }
+        </div>
+    }
+}
+
+/// The first non-empty line of the draft's code, to tell drafts apart.
+fn preview(draft: &Draft) -> &str {
+    draft
+        .files
+        .code
+        .lines()
+        .map(str::trim)
+        .find(|it| !it.is_empty())
+        .unwrap_or_default()
+}
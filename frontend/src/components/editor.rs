@@ -1,24 +1,38 @@
+use crate::api::RequestError;
+use crate::api::import::Source;
+use crate::api::share::Share;
 use crate::rc_type;
-use crate::utils::query::use_query;
-use anyhow::Result;
+use crate::utils::inline;
+use crate::utils::lsp::LspBinding;
+use crate::utils::project::{ProjectFile, ProjectFiles};
+use crate::utils::query::{use_fragment, use_query};
+use crate::utils::shortcuts::Shortcut;
+use crate::utils::yew_completions::YewCompletions;
 use monaco::api::TextModel;
-use monaco::yew::CodeEditor;
+use monaco::sys::editor::IActionDescriptor;
+use monaco::yew::{CodeEditor, CodeEditorLink};
 use monaco::{api::CodeEditorOptions, sys::editor::BuiltinTheme};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
 use yew::HtmlResult;
 use yew::prelude::*;
 use yew::suspense::use_future_with;
 
 const BASE_CONTENT: &str = crate::snippets::STABLE_SNIPPETS[0].code;
 
-rc_type!(TextContent => Option<Result<String>>);
+rc_type!(TextContent => Option<Result<Share, RequestError>>);
 
 impl TextContent {
-    fn new(val: Option<Result<String>>) -> Self {
+    fn new(val: Option<Result<Share, RequestError>>) -> Self {
         Self(Rc::new(val))
     }
-    fn new_with_string(text: String) -> Self {
-        Self::new(Some(Ok(text)))
+    fn new_with_code(code: String) -> Self {
+        Self::new(Some(Ok(Share {
+            files: ProjectFiles::with_code(code),
+            ..Share::default()
+        })))
     }
 }
 
@@ -29,28 +43,119 @@ fn get_options() -> CodeEditorOptions {
         .with_automatic_layout(true)
 }
 
+/// One Monaco model per editor tab, in [`ProjectFile::ALL`] order.
+#[derive(PartialEq)]
+struct Models(Vec<(ProjectFile, TextModel)>);
+
+impl Models {
+    fn create(files: &ProjectFiles) -> Self {
+        Self(
+            ProjectFile::ALL
+                .into_iter()
+                .map(|file| {
+                    let model =
+                        TextModel::create(files.get(file), Some(file.language()), None).unwrap();
+                    (file, model)
+                })
+                .collect(),
+        )
+    }
+
+    fn get(&self, file: ProjectFile) -> &TextModel {
+        self.0
+            .iter()
+            .find(|(it, _)| *it == file)
+            .map(|(_, model)| model)
+            .expect("there is a model for every file")
+    }
+}
+
+/// A Monaco action running `run` on `shortcut`, listed in the editor's command palette.
+fn action(shortcut: Shortcut, keybinding: u32, run: &JsValue) -> IActionDescriptor {
+    let descriptor = js_sys::Object::new();
+    let set = |key: &str, value: &JsValue| {
+        js_sys::Reflect::set(&descriptor, &JsValue::from_str(key), value)
+            .expect("plain objects take any property");
+    };
+    set("id", &JsValue::from_str(shortcut.action_id()));
+    set("label", &JsValue::from_str(shortcut.label()));
+    set("keybindings", &js_sys::Array::of1(&keybinding.into()));
+    set("run", run);
+    descriptor.unchecked_into()
+}
+
+/// Files to put in the editor, replacing every tab. Compared by identity, so setting the same
+/// files again still replaces what was typed since.
+#[derive(Clone)]
+pub struct SetFiles(pub Rc<ProjectFiles>);
+
+impl PartialEq for SetFiles {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+
 #[derive(PartialEq, Properties)]
 pub struct EditorProps {
-    pub oninput: Callback<String>,
+    pub oninput: Callback<(ProjectFile, String)>,
     #[prop_or_default]
     pub snippet_code: Option<AttrValue>,
+    #[prop_or_default]
+    pub set_files: Option<SetFiles>,
+    /// Called with the share the editor was opened with, once it is loaded.
+    #[prop_or_default]
+    pub onload: Callback<Share>,
+    /// Called when loading the share fails. The editor then starts with the default code.
+    #[prop_or_default]
+    pub onerror: Callback<RequestError>,
+    /// Bumped to load the share again.
+    #[prop_or_default]
+    pub attempt: u32,
+    /// Called once the loaded files have been reported through `oninput`.
+    #[prop_or_default]
+    pub onready: Callback<()>,
+    #[prop_or_default]
+    pub readonly: bool,
+    /// Registers the keyboard shortcuts as editor actions, which report them here.
+    #[prop_or_default]
+    pub on_shortcut: Option<Callback<Shortcut>>,
+    /// The template version the code targets. Enables the Yew snippet completions for it, and
+    /// connects the code tab to a rust-analyzer session for completion, hover, go-to-definition
+    /// and diagnostics. Without a backend serving sessions only the snippets are offered.
+    #[prop_or_default]
+    pub version: Option<AttrValue>,
 }
 
 #[component]
 pub fn Editor(props: &EditorProps) -> HtmlResult {
     let query = use_query().unwrap();
+    let fragment = use_fragment();
+    let active = use_state(|| ProjectFile::Code);
+    let shared = query.code.is_none() && (query.shared.is_some() || !fragment.is_empty());
 
-    let text_content = use_future_with(query, |query| async move {
+    let text_content = use_future_with((query, fragment, props.attempt), |deps| async move {
+        let (query, fragment, _) = &*deps;
         if let Some(code) = &query.code {
-            return TextContent::new_with_string(code.to_string());
+            return TextContent::new_with_code(code.to_string());
+        }
+        if let Some(share) = inline::decode(fragment) {
+            return TextContent::new(Some(share));
+        }
+        let source = match (&query.gist, &query.url) {
+            (Some(id), _) => Some(Source::Gist(id)),
+            (None, Some(url)) => Some(Source::Url(url)),
+            (None, None) => None,
+        };
+        if let Some(source) = source {
+            let imported = crate::api::import::import(source).await.map(|files| Share {
+                files,
+                ..Share::default()
+            });
+            return TextContent::new(Some(imported));
         }
 
         let shared = match &query.shared {
-            Some(text) => Some(
-                crate::api::share::get(text)
-                    .await
-                    .map(|paste| paste.fields.into_content()),
-            ),
+            Some(id) => Some(crate::api::share::get(id).await),
             None => None,
         };
         TextContent::new(shared)
@@ -59,40 +164,152 @@ pub fn Editor(props: &EditorProps) -> HtmlResult {
     // Extract the text content and wrap in Rc for use as memo dependency
     let content_rc = (*text_content).clone();
 
-    let modal = use_memo(content_rc, |text_content| {
-        let text = match &**text_content {
-            Some(Ok(text)) => text,
-            Some(Err(e)) => panic!("failed to fetch data: {}", e),
-            None => BASE_CONTENT,
-        };
-        TextModel::create(text, Some("rust"), None).unwrap()
+    let models = use_memo(content_rc.clone(), |text_content| match &**text_content {
+        Some(Ok(share)) => Models::create(&share.files),
+        Some(Err(_)) | None => Models::create(&ProjectFiles::with_code(BASE_CONTENT)),
     });
 
     {
-        let modal = modal.clone();
+        let onload = props.onload.clone();
+        let onerror = props.onerror.clone();
+        use_effect_with(content_rc, move |text_content| match &**text_content {
+            Some(Ok(share)) if shared => onload.emit(share.clone()),
+            Some(Err(e)) => onerror.emit(e.clone()),
+            _ => {}
+        });
+    }
+
+    {
         let cb = props.oninput.clone();
-        use_effect_with(modal, move |modal| {
-            let modal2 = modal.clone();
-            cb.emit(modal2.get_value());
-            let disposable = modal.on_did_change_content(move |_| {
-                cb.emit(modal2.get_value());
-            });
+        let onready = props.onready.clone();
+        use_effect_with(models.clone(), move |models| {
+            let disposables: Vec<_> = models
+                .0
+                .iter()
+                .map(|(file, model)| {
+                    let file = *file;
+                    cb.emit((file, model.get_value()));
+                    let cb = cb.clone();
+                    let model2 = model.clone();
+                    model.on_did_change_content(move |_| {
+                        cb.emit((file, model2.get_value()));
+                    })
+                })
+                .collect();
+            onready.emit(());
 
-            move || drop(disposable)
+            move || drop(disposables)
         })
     }
 
     {
-        let modal = modal.clone();
+        let models = models.clone();
         let snippet_code = props.snippet_code.clone();
         use_effect_with(snippet_code, move |code| {
             if let Some(code) = code {
-                modal.set_value(code);
+                models.get(ProjectFile::Code).set_value(code);
+            }
+        });
+    }
+
+    {
+        let models = models.clone();
+        use_effect_with(props.set_files.clone(), move |files| {
+            if let Some(SetFiles(files)) = files {
+                // unchanged tabs keep their cursor and undo history
+                for (file, model) in &models.0 {
+                    if model.get_value() != files.get(*file) {
+                        model.set_value(files.get(*file));
+                    }
+                }
+            }
+        });
+    }
+
+    use_effect_with(props.version.clone(), |version| {
+        let completions = version.as_deref().map(YewCompletions::register);
+        move || drop(completions)
+    });
+
+    {
+        let models = models.clone();
+        use_effect_with((models, props.version.clone()), move |(models, version)| {
+            let binding = Rc::new(RefCell::new(None::<LspBinding>));
+            let cancelled = Rc::new(Cell::new(false));
+            if let Some(version) = version.clone() {
+                let model = models.get(ProjectFile::Code).clone();
+                let binding = binding.clone();
+                let cancelled = cancelled.clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    match LspBinding::connect(&version, model).await {
+                        // a binding finishing after cleanup is dropped right away
+                        Ok(connected) if !cancelled.get() => {
+                            *binding.borrow_mut() = Some(connected)
+                        }
+                        Ok(_) => {}
+                        Err(e) => tracing::info!(%e, "language intelligence is unavailable"),
+                    }
+                });
+            }
+            move || {
+                cancelled.set(true);
+                binding.borrow_mut().take();
             }
         });
     }
 
+    // the actions are registered once per editor and report to the latest callback
+    let on_shortcut = use_mut_ref(|| None::<Callback<Shortcut>>);
+    *on_shortcut.borrow_mut() = props.on_shortcut.clone();
+    let actions = use_mut_ref(Vec::<Closure<dyn Fn()>>::new);
+    let on_editor_created = {
+        let enabled = props.on_shortcut.is_some();
+        move |link: CodeEditorLink| {
+            if !enabled {
+                return;
+            }
+            link.with_editor(|editor| {
+                for shortcut in Shortcut::ALL {
+                    let Some(keybinding) = shortcut.keybinding() else {
+                        continue;
+                    };
+                    let on_shortcut = on_shortcut.clone();
+                    let run = Closure::<dyn Fn()>::new(move || {
+                        if let Some(on_shortcut) = &*on_shortcut.borrow() {
+                            on_shortcut.emit(shortcut);
+                        }
+                    });
+                    editor
+                        .as_ref()
+                        .add_action(&action(shortcut, keybinding, run.as_ref()));
+                    actions.borrow_mut().push(run);
+                }
+            });
+        }
+    };
+
+    let options = get_options().to_sys_options();
+    options.set_read_only(Some(props.readonly));
+
     Ok(html! {
-        <CodeEditor options={get_options().to_sys_options()} classes="the-editor h-full min-h-0" model={Some((*modal).clone())} />
+        <div class="flex flex-col h-full">
+            <div class="flex bg-gray-800 text-xs">
+                for file in ProjectFile::ALL {
+                    <button
+                        onclick={{
+                            let active = active.clone();
+                            move |_: MouseEvent| active.set(file)
+                        }}
+                        class={classes!(
+                            "px-3", "py-1", "cursor-pointer",
+                            if *active == file { "bg-[#1e1e1e] text-gray-200" } else { "text-gray-400 hover:text-gray-200" }
+                        )}
+                    >
+                        {file.label()}
+                    </button>
+                }
+            </div>
+            <CodeEditor options={options} classes="the-editor flex-grow min-h-0" model={Some(models.get(*active).clone())} {on_editor_created} />
+        </div>
     })
 }
@@ -1,4 +1,11 @@
 pub mod crates;
+pub mod drafts;
 pub mod editor;
+pub mod opt_level;
 pub mod output;
+pub mod share_dialog;
+pub mod shortcuts_help;
+pub mod size_report;
 pub mod snippets;
+pub mod toast;
+pub mod workspaces;
@@ -0,0 +1,64 @@
+use yew::prelude::*;
+
+/// Optimization levels the backend accepts, with their labels.
+pub const OPT_LEVELS: [(&str, &str); 3] = [
+    ("debug", "Debug"),
+    ("release", "Release"),
+    ("size", "Release + wasm-opt"),
+];
+
+pub fn opt_level_label(opt_level: &str) -> &'static str {
+    OPT_LEVELS
+        .iter()
+        .find(|(value, _)| *value == opt_level)
+        .map_or("Release", |(_, label)| label)
+}
+
+#[derive(Properties, PartialEq)]
+pub struct OptLevelPickerProps {
+    pub value: AttrValue,
+    pub on_select: Callback<&'static str>,
+}
+
+#[component]
+pub fn OptLevelPicker(props: &OptLevelPickerProps) -> Html {
+    let open = use_state(|| false);
+
+    html! {
+        <div class="relative">
+            <button
+                onclick={{
+                    let open = open.clone();
+                    move |_: MouseEvent| open.set(!*open)
+                }}
+                title="Optimization level"
+                class="p-3 text-sm cursor-pointer bg-gray-800 rounded-md shadow-lg text-gray-400 hover:bg-gray-900 flex items-center gap-1"
+            >
+                {opt_level_label(&props.value)}
+                <span class="text-xs">{if *open { "▲" } else { "▼" }}</span>
+            </button>
+            if *open {
+                <div class="absolute left-0 top-full mt-1 z-50 bg-gray-800 border border-gray-600 rounded-md shadow-xl py-1 min-w-[160px]">
+                    for (value, label) in OPT_LEVELS {
+                        <button
+                            onclick={{
+                                let on_select = props.on_select.clone();
+                                let open = open.clone();
+                                move |_: MouseEvent| {
+                                    on_select.emit(value);
+                                    open.set(false);
+                                }
+                            }}
+                            class={classes!(
+                                "w-full", "text-left", "px-3", "py-2", "text-sm", "hover:bg-gray-700", "cursor-pointer",
+                                if props.value == value { "text-gray-100" } else { "text-gray-300" }
+                            )}
+                        >
+                            {label}
+                        </button>
+                    }
+                </div>
+            }
+        </div>
+    }
+}
@@ -1,42 +1,326 @@
-use crate::api::BACKEND_URL;
-use crate::{ActionButtonState, ActionButtonStateContext};
+use crate::api::{ApiErrorBody, ApiErrorKind, BACKEND_URL};
+use crate::components::opt_level::opt_level_label;
+use crate::components::size_report::{SizeReport, SizeReportView};
+use crate::utils::project::ProjectFiles;
+use crate::{ActionButtonState, ActionButtonStateContext, icon};
 use gloo::timers::callback::Interval;
-use gloo_net::http::{QueryParams, Request};
+use gloo_net::http::{Request, Response};
 use std::cell::Cell;
 use std::rc::Rc;
+use std::time::Duration;
 use yew::HtmlResult;
+use yew::platform::time::sleep;
 use yew::prelude::*;
 use yew::suspense::use_future_with;
 
-#[derive(Properties, PartialEq, Eq)]
-pub struct OutputContainerProps {
-    pub value: Rc<str>,
+/// Options a run is compiled with, picked in the header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunSettings {
     pub version: AttrValue,
+    pub opt_level: AttrValue,
+    /// Generate Tailwind CSS for the classes the app uses.
+    pub tailwind: bool,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct OutputContainerProps {
+    pub files: Rc<ProjectFiles>,
+    pub settings: RunSettings,
+    pub on_retry: Callback<()>,
+    /// Called with the outcome once the run finished.
+    #[prop_or_default]
+    pub on_finish: Callback<RunOutcome>,
+}
+
+/// How a run ended, e.g. for pages embedding the playground.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RunOutcome {
+    Ok { url: String, wasm_size: u64 },
+    CompileFailed,
+    Failed { message: String },
+}
+
+/// Controls how often and how fast [`compile`] retries a request the backend could not serve.
+#[derive(Debug, Clone, Copy)]
+struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+// Cloud Run cold starts can take a while, so the budget is generous enough to ride one out
+const RETRY_POLICY: RetryPolicy = RetryPolicy {
+    max_attempts: 6,
+    base_delay: Duration::from_millis(500),
+    max_delay: Duration::from_secs(10),
+};
+
+impl RetryPolicy {
+    /// Exponential backoff with full jitter: a random delay up to [`RetryPolicy::backoff`].
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff(attempt).mul_f64(js_sys::Math::random())
+    }
+
+    /// The longest delay before retrying `attempt`: `base_delay * 2^attempt`, capped at
+    /// `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_delay)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CompileError {
+    #[error("the compilation took too long and was cancelled by the backend")]
+    Timeout,
+    #[error("the backend is busy with other compilations")]
+    Busy,
+    #[error("the backend failed with status {status}: {message}")]
+    Server { status: u16, message: String },
+    /// A gateway error from a proxy in front of the backend, e.g. while an instance starts.
+    #[error("the backend is unavailable, status {status}: {message}")]
+    Unavailable { status: u16, message: String },
+    #[error("the backend rejected the request with status {status}: {message}")]
+    Rejected { status: u16, message: String },
+    #[error("could not reach the backend: {0}")]
+    Network(String),
+}
+
+impl CompileError {
+    /// Classifies a response without our JSON body, as proxies send them.
+    fn from_status(status: u16, message: String) -> Self {
+        match status {
+            408 => Self::Timeout,
+            429 => Self::Busy,
+            502..=504 => Self::Unavailable { status, message },
+            500..=599 => Self::Server { status, message },
+            _ => Self::Rejected { status, message },
+        }
+    }
+
+    fn from_body(status: u16, body: ApiErrorBody) -> Self {
+        match body.kind {
+            ApiErrorKind::Timeout => Self::Timeout,
+            ApiErrorKind::QueueFull | ApiErrorKind::RateLimited => Self::Busy,
+            ApiErrorKind::InvalidInput
+            | ApiErrorKind::NotFound
+            | ApiErrorKind::TooLarge
+            | ApiErrorKind::Forbidden => Self::Rejected {
+                status,
+                message: body.message,
+            },
+            ApiErrorKind::Internal => Self::Server {
+                status,
+                message: body.message,
+            },
+            // the backend answered itself, so a 5xx is its own error and not a gateway's
+            ApiErrorKind::CompileFailed | ApiErrorKind::Unknown => {
+                match Self::from_status(status, body.message) {
+                    Self::Unavailable { status, message } => Self::Server { status, message },
+                    error => error,
+                }
+            }
+        }
+    }
+
+    /// Rate limiting, gateway errors and network failures are transient and worth another
+    /// attempt. Errors the backend reports itself, such as a failing wasm-opt or upload, would
+    /// only repeat with another full compile, and a timeout means the backend already spent its
+    /// whole compile timeout on the code.
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Busy | Self::Unavailable { .. } | Self::Network(_)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompileFailure {
+    pub attempts: u32,
+    pub error: CompileError,
+}
+
+/// Reads the `Retry-After` header, if the backend sent one in seconds.
+fn retry_after(resp: &Response) -> Option<Duration> {
+    resp.headers()
+        .get("retry-after")
+        .as_deref()
+        .and_then(parse_retry_after)
+}
+
+/// A `Retry-After` value in seconds. The HTTP date form is not used by the backend.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// What a failed response turned out to be: a compile error to show to the user, or a request
+/// failure that may be retried.
+enum Failed {
+    Compilation(String),
+    Request(CompileError),
+}
+
+async fn read_failure(resp: &Response) -> Failed {
+    let status = resp.status();
+    // Proxies in front of the backend (e.g. Cloud Run) answer with plain text, not our JSON body
+    let text = resp.text().await.unwrap_or_default();
+    let Ok(body) = serde_json::from_str::<ApiErrorBody>(&text) else {
+        let message = if text.is_empty() {
+            resp.status_text()
+        } else {
+            text
+        };
+        return Failed::Request(CompileError::from_status(status, message));
+    };
+
+    let html = body
+        .details
+        .as_ref()
+        .and_then(|it| it.get("html"))
+        .and_then(|it| it.as_str());
+    match (body.kind, html) {
+        (ApiErrorKind::CompileFailed, Some(html)) => Failed::Compilation(html.to_string()),
+        _ => Failed::Request(CompileError::from_body(status, body)),
+    }
+}
+
+/// What the output frame shows for a finished compilation.
+#[derive(Debug, Clone, PartialEq)]
+enum CompileOutput {
+    /// URL of the compiled app, served by the backend, and the size of its wasm in bytes.
+    App {
+        url: AttrValue,
+        wasm_size: u64,
+        size_report: Option<SizeReport>,
+    },
+    /// The compiler's error output, rendered as HTML.
+    Errors(AttrValue),
+}
+
+#[derive(serde::Deserialize)]
+struct RunResponse {
+    id: String,
+    wasm_size: u64,
+    #[serde(default)]
+    size_report: Option<SizeReport>,
+}
+
+async fn read_success(resp: &Response) -> Result<CompileOutput, CompileError> {
+    let RunResponse {
+        id,
+        wasm_size,
+        size_report,
+    } = resp.json().await.map_err(|e| CompileError::Server {
+        status: resp.status(),
+        message: format!("invalid response: {e}"),
+    })?;
+    let url = format!("{BACKEND_URL}/artifacts/{id}/index.html");
+    Ok(CompileOutput::App {
+        url: url.into(),
+        wasm_size,
+        size_report,
+    })
 }
 
-async fn compile(code: &str, version: &str) -> String {
-    let query = QueryParams::new();
-    query.append("code", code);
-    query.append("version", version);
-    let url = format!("{}/run?{}", BACKEND_URL, query);
+#[derive(serde::Serialize)]
+struct RunPayload<'a> {
+    version: &'a str,
+    opt: &'a str,
+    analyze: bool,
+    tailwind: bool,
+    #[serde(flatten)]
+    files: &'a ProjectFiles,
+}
+
+async fn send_run(url: &str, payload: &RunPayload<'_>) -> Result<Response, gloo_net::Error> {
+    Request::post(url).json(payload)?.send().await
+}
 
+/// Compiles `files`, with a size report if `analyze` is set. The backend serves a build it did
+/// before from its cache, so asking for the report of the last run does not compile again.
+async fn compile(
+    files: &ProjectFiles,
+    settings: &RunSettings,
+    analyze: bool,
+) -> Result<CompileOutput, CompileFailure> {
+    let url = format!("{}/run", BACKEND_URL);
+    let payload = RunPayload {
+        version: &settings.version,
+        opt: &settings.opt_level,
+        analyze,
+        tailwind: settings.tailwind,
+        files,
+    };
+
+    let mut attempt = 0;
     loop {
-        match Request::get(&url).send().await {
-            Ok(resp) if resp.status() == 504 => continue,
-            Ok(resp) => return resp.text().await.unwrap_or_default(),
-            Err(_) => continue,
+        let (error, min_delay) = match send_run(&url, &payload).await {
+            Ok(resp) if resp.ok() => match read_success(&resp).await {
+                Ok(output) => return Ok(output),
+                Err(error) => (error, None),
+            },
+            Ok(resp) => match read_failure(&resp).await {
+                Failed::Compilation(html) => return Ok(CompileOutput::Errors(html.into())),
+                Failed::Request(error) => (error, retry_after(&resp)),
+            },
+            Err(e) => (CompileError::Network(e.to_string()), None),
+        };
+        attempt += 1;
+
+        if !error.is_retryable() || attempt >= RETRY_POLICY.max_attempts {
+            return Err(CompileFailure {
+                attempts: attempt,
+                error,
+            });
         }
+
+        let delay = RETRY_POLICY
+            .delay(attempt)
+            .max(min_delay.unwrap_or_default());
+        tracing::warn!(%error, attempt, ?delay, "compile request failed, retrying");
+        sleep(delay).await;
+    }
+}
+
+/// The size report of a run, fetched when the Size tab is first opened.
+#[derive(Debug, Clone, PartialEq)]
+enum SizeAnalysis {
+    Loading,
+    Done(SizeReport),
+    Failed(String),
+}
+
+async fn analyze(files: &ProjectFiles, settings: &RunSettings) -> SizeAnalysis {
+    match compile(files, settings, true).await {
+        Ok(CompileOutput::App {
+            size_report: Some(report),
+            ..
+        }) => SizeAnalysis::Done(report),
+        Ok(_) => SizeAnalysis::Failed("the backend could not analyze the wasm".to_string()),
+        Err(failure) => SizeAnalysis::Failed(failure.error.to_string()),
     }
 }
 
+/// Tabs of the output panel for a successful compilation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputTab {
+    App,
+    Size,
+}
+
 #[component]
 pub fn OutputContainer(props: &OutputContainerProps) -> HtmlResult {
     let action_button_state = use_context::<ActionButtonStateContext>().unwrap();
+    let tab = use_state(|| OutputTab::App);
+    let analysis = use_state(|| None::<SizeAnalysis>);
 
-    let value = Rc::clone(&props.value);
-    let version = props.version.clone();
-    let result = use_future_with((value, version), |deps| async move {
-        compile(&deps.0, &deps.1).await
+    let files = Rc::clone(&props.files);
+    let settings = props.settings.clone();
+    let result = use_future_with((files, settings), |deps| async move {
+        compile(&deps.0, &deps.1, false).await
     })?;
 
     {
@@ -46,7 +330,147 @@ pub fn OutputContainer(props: &OutputContainerProps) -> HtmlResult {
         });
     }
 
-    Ok(html! { <iframe srcdoc={(*result).clone()} class="w-full h-full" /> })
+    let outcome = match &*result {
+        Ok(CompileOutput::App { url, wasm_size, .. }) => RunOutcome::Ok {
+            url: url.to_string(),
+            wasm_size: *wasm_size,
+        },
+        Ok(CompileOutput::Errors(_)) => RunOutcome::CompileFailed,
+        Err(failure) => RunOutcome::Failed {
+            message: failure.error.to_string(),
+        },
+    };
+    {
+        let on_finish = props.on_finish.clone();
+        use_effect_with(outcome, move |outcome| on_finish.emit(outcome.clone()));
+    }
+
+    Ok(match &*result {
+        Ok(CompileOutput::App { url, wasm_size, .. }) => {
+            let tab_button = |value: OutputTab, label: &'static str| {
+                let onclick = {
+                    let tab = tab.clone();
+                    let analysis = analysis.clone();
+                    let files = Rc::clone(&props.files);
+                    let settings = props.settings.clone();
+                    move |_: MouseEvent| {
+                        tab.set(value);
+                        if value == OutputTab::Size && analysis.is_none() {
+                            analysis.set(Some(SizeAnalysis::Loading));
+                            let analysis = analysis.clone();
+                            let files = Rc::clone(&files);
+                            let settings = settings.clone();
+                            yew::platform::spawn_local(async move {
+                                analysis.set(Some(analyze(&files, &settings).await));
+                            });
+                        }
+                    }
+                };
+                html! {
+                    <button {onclick} class={classes!(
+                        "px-3", "py-1", "cursor-pointer",
+                        if *tab == value { "bg-gray-700 text-gray-200" } else { "text-gray-400 hover:text-gray-200" }
+                    )}>{label}</button>
+                }
+            };
+            html! {
+                <div class="flex flex-col h-full">
+                    <div class="flex items-center justify-between text-xs bg-gray-800">
+                        <div class="flex">
+                            {tab_button(OutputTab::App, "App")}
+                            {tab_button(OutputTab::Size, "Size")}
+                        </div>
+                        <span class="px-3 text-gray-400">
+                            {format!(
+                                "app_bg.wasm: {} ({})",
+                                format_size(*wasm_size),
+                                opt_level_label(&props.settings.opt_level),
+                            )}
+                        </span>
+                    </div>
+                    // kept mounted so switching tabs does not restart the app
+                    <iframe src={url.clone()} class={classes!("w-full", "flex-grow", (*tab != OutputTab::App).then_some("hidden"))} />
+                    if *tab == OutputTab::Size {
+                        <div class="flex-grow min-h-0">
+                            {match &*analysis {
+                                Some(SizeAnalysis::Done(report)) => html! {
+                                    <SizeReportView report={report.clone()} />
+                                },
+                                Some(SizeAnalysis::Failed(message)) => html! {
+                                    <div class="p-3 text-sm text-gray-300">
+                                        {format!("Could not analyze the wasm: {message}")}
+                                    </div>
+                                },
+                                Some(SizeAnalysis::Loading) | None => html! {
+                                    <div class="p-3 text-sm text-gray-400">{"Analyzing the wasm..."}</div>
+                                },
+                            }}
+                        </div>
+                    }
+                </div>
+            }
+        }
+        Ok(CompileOutput::Errors(html)) => {
+            html! { <iframe srcdoc={html.clone()} class="w-full h-full" /> }
+        }
+        Err(failure) => html! {
+            <CompileErrorView failure={failure.clone()} on_retry={props.on_retry.clone()} />
+        },
+    })
+}
+
+/// Formats a byte count for humans, e.g. `1.5 KiB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[derive(Properties, PartialEq)]
+struct CompileErrorViewProps {
+    failure: CompileFailure,
+    on_retry: Callback<()>,
+}
+
+#[component]
+fn CompileErrorView(props: &CompileErrorViewProps) -> Html {
+    let CompileFailure { attempts, error } = &props.failure;
+    let title = match error {
+        CompileError::Timeout => "Compilation timed out",
+        CompileError::Busy => "The playground is busy",
+        CompileError::Server { .. } => "The backend ran into an error",
+        CompileError::Unavailable { .. } => "The backend is unavailable",
+        CompileError::Rejected { .. } => "The backend rejected the request",
+        CompileError::Network(_) => "The backend is unreachable",
+    };
+    let attempts = if *attempts == 1 {
+        "Gave up after 1 attempt".to_string()
+    } else {
+        format!("Gave up after {attempts} attempts")
+    };
+    let onclick = props.on_retry.reform(|_: MouseEvent| ());
+
+    html! {
+        <div class="h-full bg-gray-600 flex items-center justify-center">
+            <div class="text-gray-200 flex flex-col items-center gap-3 max-w-xl text-center">
+                <span class="text-lg font-semibold">{title}</span>
+                <span class="text-sm text-gray-300">{error.to_string()}</span>
+                <span class="text-xs text-gray-400">{attempts}</span>
+                <button {onclick} class="p-3 text-sm shadow-lg bg-gray-800 hover:bg-gray-900 rounded-md flex items-center gap-2">
+                    {icon!("refresh", classes!("fill-gray-200", "w-4", "h-4"))}
+                    {"Retry"}
+                </button>
+            </div>
+        </div>
+    }
 }
 
 #[component]
@@ -79,3 +503,52 @@ pub fn CompileTimer() -> Html {
         </div>
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially_up_to_the_cap() {
+        let backoff = |attempt| RETRY_POLICY.backoff(attempt);
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(4), Duration::from_secs(8));
+        assert_eq!(backoff(5), Duration::from_secs(10));
+        // large attempts neither overflow nor exceed the cap
+        assert_eq!(backoff(40), Duration::from_secs(10));
+        assert_eq!(backoff(u32::MAX), Duration::from_secs(10));
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        assert_eq!(parse_retry_after("5"), Some(Duration::from_secs(5)));
+        assert_eq!(parse_retry_after(" 12 "), Some(Duration::from_secs(12)));
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("1.5"), None);
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+    }
+
+    #[test]
+    fn retries_only_transient_failures() {
+        assert!(CompileError::from_status(429, String::new()).is_retryable());
+        assert!(CompileError::from_status(503, String::new()).is_retryable());
+        assert!(CompileError::Network(String::new()).is_retryable());
+        assert!(!CompileError::from_status(408, String::new()).is_retryable());
+        assert!(!CompileError::from_status(400, String::new()).is_retryable());
+        assert!(!CompileError::from_status(500, String::new()).is_retryable());
+    }
+
+    #[test]
+    fn backend_errors_are_final() {
+        let body = |kind| ApiErrorBody {
+            kind,
+            message: "wasm-opt failed".to_string(),
+            details: None,
+        };
+        assert!(!CompileError::from_body(500, body(ApiErrorKind::Internal)).is_retryable());
+        assert!(!CompileError::from_body(503, body(ApiErrorKind::Unknown)).is_retryable());
+        assert!(CompileError::from_body(503, body(ApiErrorKind::QueueFull)).is_retryable());
+        assert!(CompileError::from_body(429, body(ApiErrorKind::RateLimited)).is_retryable());
+    }
+}
@@ -0,0 +1,151 @@
+use wasm_bindgen_futures::JsFuture;
+use yew::prelude::*;
+
+use crate::icon;
+use crate::utils::inline::MAX_FRAGMENT_LEN;
+
+fn origin() -> String {
+    gloo::utils::window()
+        .location()
+        .origin()
+        .unwrap_or_default()
+}
+
+/// Starts writing `text` to the clipboard. Browsers only allow this while handling a click, so
+/// call it from the handler itself rather than from a spawned future.
+fn copy_to_clipboard(text: &str) -> JsFuture {
+    let clipboard = gloo::utils::window().navigator().clipboard();
+    JsFuture::from(clipboard.write_text(text))
+}
+
+/// Whether the last copy of the permalink worked, shown below it.
+#[derive(Clone, Copy, PartialEq)]
+enum Copied {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ShareDialogProps {
+    /// Path and query or fragment of the shared project, e.g. `/?shared={id}`.
+    pub path: AttrValue,
+    /// Path with the project encoded in the fragment, if it fits. The same as `path` when the
+    /// project was not stored on the backend.
+    pub inline_path: Option<AttrValue>,
+    pub on_close: Callback<()>,
+}
+
+/// Shown after sharing: the permalink and other ways of linking to the share, each with a copy
+/// button. The link is not copied on its own: by the time a stored share has its id, the click
+/// on Share no longer counts as the user's and browsers refuse clipboard writes.
+#[component]
+pub fn ShareDialog(props: &ShareDialogProps) -> Html {
+    let permalink = format!("{}{}", origin(), props.path);
+    let copied = use_state(|| Copied::Pending);
+    let on_copy = {
+        let copied = copied.clone();
+        Callback::from(move |done: bool| {
+            copied.set(if done { Copied::Done } else { Copied::Failed })
+        })
+    };
+
+    // paths start with the `/` of the playground itself
+    let embed = format!(
+        r#"<iframe src="{}/embed{}" width="100%" height="500" style="border: 0"></iframe>"#,
+        origin(),
+        &props.path[1..]
+    );
+    let markdown = format!(
+        "[![Open in the Yew Playground](https://img.shields.io/badge/open%20in-Yew%20Playground-009a5b)]({permalink})"
+    );
+    let inline = props.inline_path.as_ref() == Some(&props.path);
+    let storage = match &props.inline_path {
+        Some(_) if inline => {
+            "The project is stored in the link itself, nothing was uploaded.".to_string()
+        }
+        Some(_) => "The project was stored on the playground so it links to the project it was \
+            forked from. The inline link carries the code itself."
+            .to_string(),
+        None => format!(
+            "The project is too large to fit in a link ({MAX_FRAGMENT_LEN} characters), so it was stored on the playground."
+        ),
+    };
+    let inline_link = props
+        .inline_path
+        .as_ref()
+        .filter(|_| !inline)
+        .map(|path| format!("{}{path}", origin()));
+
+    let status = match *copied {
+        Copied::Pending => "Copy the link with the button next to it",
+        Copied::Done => "Copied to the clipboard",
+        Copied::Failed => "Could not copy, select the link to copy it",
+    };
+
+    html! {
+        <div class="fixed inset-0 z-50 bg-black/50 flex items-center justify-center" onclick={props.on_close.reform(|_: MouseEvent| ())}>
+            <div
+                role="dialog"
+                aria-label="Share"
+                class="bg-gray-800 rounded-md shadow-xl p-5 w-[36rem] max-w-full text-gray-200 flex flex-col gap-4"
+                onclick={|e: MouseEvent| e.stop_propagation()}
+            >
+                <div class="flex justify-between">
+                    <span class="text-lg font-semibold">{"Share"}</span>
+                    <button onclick={props.on_close.reform(|_: MouseEvent| ())} title="Close" class="text-gray-400 hover:text-gray-200 cursor-pointer">{"✕"}</button>
+                </div>
+                <ShareField label="Permalink" value={permalink} {on_copy} />
+                <span class="text-xs text-gray-400 -mt-3">{status}</span>
+                <ShareField label="Embed" value={embed} />
+                <ShareField label="Markdown" value={markdown} />
+                if let Some(inline_link) = inline_link {
+                    <ShareField label="Link with code inline" value={inline_link} />
+                }
+                <span class="text-xs text-gray-400">{storage}</span>
+            </div>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct ShareFieldProps {
+    label: AttrValue,
+    value: AttrValue,
+    /// Told whether copying worked.
+    #[prop_or_default]
+    on_copy: Callback<bool>,
+}
+
+#[component]
+fn ShareField(props: &ShareFieldProps) -> Html {
+    let onclick = {
+        let value = props.value.clone();
+        let on_copy = props.on_copy.clone();
+        move |_: MouseEvent| {
+            let copy = copy_to_clipboard(&value);
+            let on_copy = on_copy.clone();
+            yew::platform::spawn_local(async move {
+                on_copy.emit(copy.await.is_ok());
+            });
+        }
+    };
+
+    html! {
+        <label class="flex flex-col gap-1 text-sm">
+            <span class="text-gray-400">{&props.label}</span>
+            <div class="flex gap-2">
+                <input
+                    type="text"
+                    readonly=true
+                    value={&props.value}
+                    onfocus={|e: FocusEvent| e.target_unchecked_into::<web_sys::HtmlInputElement>().select()}
+                    class="flex-grow p-2 bg-gray-900 rounded-md font-mono text-xs text-gray-200"
+                />
+                <button {onclick} title="Copy" class="p-2 bg-gray-700 hover:bg-gray-900 rounded-md cursor-pointer">
+                    {icon!("content_copy", classes!("fill-gray-200", "w-4", "h-4"))}
+                </button>
+            </div>
+        </label>
+    }
+}
@@ -0,0 +1,42 @@
+use yew::prelude::*;
+
+use crate::utils::shortcuts::Shortcut;
+
+#[derive(Properties, PartialEq)]
+pub struct ShortcutsHelpProps {
+    pub on_close: Callback<()>,
+}
+
+/// Lists the keyboard shortcuts. Closes on a click outside of it, `App` closes it on `Escape`.
+#[component]
+pub fn ShortcutsHelp(props: &ShortcutsHelpProps) -> Html {
+    html! {
+        <div
+            class="fixed inset-0 z-50 bg-black/50 flex items-center justify-center"
+            onclick={props.on_close.reform(|_: MouseEvent| ())}
+        >
+            <div
+                role="dialog"
+                aria-label="Keyboard shortcuts"
+                class="bg-gray-800 rounded-md shadow-xl p-5 w-[28rem] max-w-full text-gray-200 flex flex-col gap-4"
+                onclick={|e: MouseEvent| e.stop_propagation()}
+            >
+                <div class="flex justify-between">
+                    <span class="text-lg font-semibold">{"Keyboard shortcuts"}</span>
+                    <button onclick={props.on_close.reform(|_: MouseEvent| ())} title="Close" class="text-gray-400 hover:text-gray-200 cursor-pointer">{"✕"}</button>
+                </div>
+                <table class="text-sm">
+                    for shortcut in Shortcut::ALL {
+                        <tr>
+                            <td class="py-1 text-gray-300">{shortcut.label()}</td>
+                            <td class="py-1 text-right">
+                                <kbd class="px-2 py-0.5 bg-gray-900 rounded font-mono text-xs text-gray-200">{shortcut.keys()}</kbd>
+                            </td>
+                        </tr>
+                    }
+                </table>
+                <span class="text-xs text-gray-400">{"While the editor has focus, its command palette (F1) lists the same actions."}</span>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,94 @@
+use serde::Deserialize;
+use yew::prelude::*;
+
+use crate::components::output::format_size;
+
+/// Size breakdown of the compiled wasm, as reported by the backend.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SizeReport {
+    pub total_size: u64,
+    pub functions: Vec<FunctionSize>,
+    pub crates: Vec<CrateSize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FunctionSize {
+    pub name: String,
+    pub shallow_size: u64,
+    pub retained_size: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CrateSize {
+    pub name: String,
+    pub size: u64,
+    pub percent: f64,
+    pub functions: usize,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct SizeReportViewProps {
+    pub report: SizeReport,
+}
+
+#[component]
+pub fn SizeReportView(props: &SizeReportViewProps) -> Html {
+    let report = &props.report;
+    let th = "px-3 py-2 font-semibold text-left";
+    let td = "px-3 py-1 text-right tabular-nums";
+
+    html! {
+        <div class="h-full overflow-auto bg-gray-700 text-gray-200 text-sm p-3 flex flex-col gap-4">
+            <span class="text-gray-300">{format!("Total: {}", format_size(report.total_size))}</span>
+
+            <section>
+                <h2 class="font-semibold mb-2">{"Crates"}</h2>
+                <table class="w-full">
+                    <thead class="bg-gray-800 text-gray-400">
+                        <tr>
+                            <th class={th}>{"Crate"}</th>
+                            <th class={th}>{"Size"}</th>
+                            <th class={th}>{"%"}</th>
+                            <th class={th}>{"Functions"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        for krate in &report.crates {
+                            <tr class="odd:bg-gray-600">
+                                <td class="px-3 py-1">{&krate.name}</td>
+                                <td class={td}>{format_size(krate.size)}</td>
+                                <td class={td}>{format!("{:.1}", krate.percent)}</td>
+                                <td class={td}>{krate.functions}</td>
+                            </tr>
+                        }
+                    </tbody>
+                </table>
+            </section>
+
+            <section>
+                <h2 class="font-semibold mb-2">{"Largest functions"}</h2>
+                <table class="w-full">
+                    <thead class="bg-gray-800 text-gray-400">
+                        <tr>
+                            <th class={th}>{"Function"}</th>
+                            <th class={th} title="Size of the function itself">{"Shallow"}</th>
+                            <th class={th} title="Size saved by removing the function and everything only it uses">{"Retained"}</th>
+                            <th class={th}>{"%"}</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        for function in &report.functions {
+                            <tr class="odd:bg-gray-600">
+                                <td class="px-3 py-1 font-mono break-all">{&function.name}</td>
+                                <td class={td}>{format_size(function.shallow_size)}</td>
+                                <td class={td}>{format_size(function.retained_size)}</td>
+                                <td class={td}>{format!("{:.1}", function.percent)}</td>
+                            </tr>
+                        }
+                    </tbody>
+                </table>
+            </section>
+        </div>
+    }
+}
@@ -0,0 +1,42 @@
+use yew::prelude::*;
+
+use crate::icon;
+
+#[derive(Properties, PartialEq)]
+pub struct ToastProps {
+    pub title: AttrValue,
+    pub message: AttrValue,
+    /// Shows a retry button when set.
+    #[prop_or_default]
+    pub on_retry: Option<Callback<()>>,
+    pub on_dismiss: Callback<()>,
+}
+
+/// A notice in the bottom right corner, e.g. for a failed request.
+#[component]
+pub fn Toast(props: &ToastProps) -> Html {
+    html! {
+        <div role="alert" class="fixed bottom-4 right-4 z-50 max-w-sm bg-gray-800 border border-red-400 rounded-md shadow-xl p-4 text-gray-200 flex flex-col gap-2">
+            <div class="flex justify-between gap-4">
+                <span class="font-semibold">{&props.title}</span>
+                <button
+                    onclick={props.on_dismiss.reform(|_: MouseEvent| ())}
+                    title="Dismiss"
+                    class="text-gray-400 hover:text-gray-200 cursor-pointer"
+                >
+                    {"✕"}
+                </button>
+            </div>
+            <span class="text-sm text-gray-300">{&props.message}</span>
+            if let Some(on_retry) = &props.on_retry {
+                <button
+                    onclick={on_retry.reform(|_: MouseEvent| ())}
+                    class="self-end p-2 text-sm shadow-lg bg-gray-700 hover:bg-gray-900 rounded-md flex items-center gap-2 cursor-pointer"
+                >
+                    {icon!("refresh", classes!("fill-gray-200", "w-4", "h-4"))}
+                    {"Retry"}
+                </button>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,249 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use wasm_bindgen_futures::JsFuture;
+use yew::prelude::*;
+
+use crate::api::share::ShareSettings;
+use crate::icon;
+use crate::snippets::snippets_for;
+use crate::utils::project::ProjectFiles;
+use crate::utils::workspaces::{self, Workspace, WorkspaceError};
+
+const EXPORT_FILE_NAME: &str = "yew-playground-projects.json";
+
+/// A change to the stored workspaces.
+type Task = Pin<Box<dyn Future<Output = Result<(), WorkspaceError>>>>;
+
+/// Stores a new workspace and opens it.
+fn create(workspace: Workspace, on_open: Callback<Workspace>) -> Task {
+    Box::pin(async move {
+        workspaces::save(std::slice::from_ref(&workspace)).await?;
+        on_open.emit(workspace);
+        Ok(())
+    })
+}
+
+#[derive(Properties, PartialEq)]
+pub struct WorkspacesSidebarProps {
+    /// Id of the open workspace.
+    pub current: Option<AttrValue>,
+    pub version: AttrValue,
+    /// A new workspace with the editor's contents, named by the argument.
+    pub snapshot: Callback<String, Workspace>,
+    pub on_open: Callback<Workspace>,
+    pub on_rename: Callback<Workspace>,
+    pub on_delete: Callback<AttrValue>,
+}
+
+/// The named projects saved in the browser, to switch between them.
+#[component]
+pub fn WorkspacesSidebar(props: &WorkspacesSidebarProps) -> Html {
+    let workspaces = use_state(Vec::<Workspace>::new);
+    let error = use_state(|| None::<WorkspaceError>);
+    // bumped after every change to read the list again
+    let reload = use_state(|| 0u32);
+
+    {
+        let workspaces = workspaces.clone();
+        let error = error.clone();
+        use_effect_with((*reload, props.current.clone()), move |_| {
+            yew::platform::spawn_local(async move {
+                match workspaces::list().await {
+                    Ok(list) => workspaces.set(list),
+                    Err(e) => error.set(Some(e)),
+                }
+            });
+        });
+    }
+
+    // runs a change, then shows its error or the updated list
+    let change = {
+        let error = error.clone();
+        let reload = reload.clone();
+        move |task: Task| {
+            let error = error.clone();
+            let reload = reload.clone();
+            yew::platform::spawn_local(async move {
+                match task.await {
+                    Ok(()) => error.set(None),
+                    Err(e) => error.set(Some(e)),
+                }
+                reload.set(*reload + 1);
+            });
+        }
+    };
+
+    let on_new = {
+        let change = change.clone();
+        let version = props.version.clone();
+        let on_open = props.on_open.clone();
+        move |_: MouseEvent| {
+            let Some(name) = gloo::dialogs::prompt("Name of the new project", Some("Untitled"))
+            else {
+                return;
+            };
+            let files = ProjectFiles::with_code(snippets_for(&version)[0].code);
+            let settings = ShareSettings::default();
+            let workspace = Workspace::new(name, version.to_string(), files, settings);
+            change(create(workspace, on_open.clone()));
+        }
+    };
+
+    let on_save_current = {
+        let change = change.clone();
+        let snapshot = props.snapshot.clone();
+        let on_open = props.on_open.clone();
+        move |_: MouseEvent| {
+            let Some(name) = gloo::dialogs::prompt("Name of the project", Some("Untitled")) else {
+                return;
+            };
+            change(create(snapshot.emit(name), on_open.clone()));
+        }
+    };
+
+    let on_export = {
+        let workspaces = workspaces.clone();
+        move |_: MouseEvent| {
+            let json = workspaces::export((*workspaces).clone());
+            crate::utils::save_file(json.as_bytes(), "application/json", EXPORT_FILE_NAME);
+        }
+    };
+
+    let on_import = {
+        let change = change.clone();
+        move |e: Event| {
+            let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|it| it.get(0)) else {
+                return;
+            };
+            // the same file can be picked again
+            input.set_value("");
+            change(Box::pin(async move {
+                let text = JsFuture::from(file.text()).await?;
+                let imported = workspaces::import(&text.as_string().unwrap_or_default())?;
+                workspaces::save(&imported).await
+            }));
+        }
+    };
+
+    let item = |workspace: &Workspace| {
+        let is_current = props.current.as_deref() == Some(workspace.id.as_str());
+        let on_open = {
+            let on_open = props.on_open.clone();
+            let workspace = workspace.clone();
+            move |_: MouseEvent| on_open.emit(workspace.clone())
+        };
+        let on_rename = {
+            let change = change.clone();
+            let on_rename = props.on_rename.clone();
+            let workspace = workspace.clone();
+            move |_: MouseEvent| {
+                let Some(name) = gloo::dialogs::prompt("New name", Some(&workspace.name)) else {
+                    return;
+                };
+                let workspace = Workspace {
+                    name,
+                    updated_at: js_sys::Date::now(),
+                    ..workspace.clone()
+                };
+                let on_rename = on_rename.clone();
+                change(Box::pin(async move {
+                    workspaces::save(std::slice::from_ref(&workspace)).await?;
+                    on_rename.emit(workspace);
+                    Ok::<_, WorkspaceError>(())
+                }));
+            }
+        };
+        let on_duplicate = {
+            let change = change.clone();
+            let workspace = workspace.clone();
+            move |_: MouseEvent| {
+                let copy = workspace.copy(format!("{} (copy)", workspace.name));
+                change(Box::pin(async move {
+                    workspaces::save(std::slice::from_ref(&copy)).await
+                }));
+            }
+        };
+        let on_delete = {
+            let change = change.clone();
+            let on_delete = props.on_delete.clone();
+            let workspace = workspace.clone();
+            move |_: MouseEvent| {
+                if !gloo::dialogs::confirm(&format!("Delete \"{}\"?", workspace.name)) {
+                    return;
+                }
+                let on_delete = on_delete.clone();
+                let id = workspace.id.clone();
+                change(Box::pin(async move {
+                    workspaces::delete(&id).await?;
+                    on_delete.emit(AttrValue::from(id));
+                    Ok::<_, WorkspaceError>(())
+                }));
+            }
+        };
+        let action = "p-1 rounded cursor-pointer hover:bg-gray-900";
+        html! {
+            <li
+                key={workspace.id.clone()}
+                class={classes!(
+                    "group", "flex", "items-center", "gap-1", "rounded-md", "px-2", "py-1",
+                    if is_current { "bg-gray-900 text-gray-200" } else { "text-gray-400 hover:bg-gray-700" }
+                )}
+            >
+                <button onclick={on_open} title={workspace.version.clone()} class="flex-grow truncate text-left text-sm cursor-pointer">
+                    {&workspace.name}
+                </button>
+                <button onclick={on_rename} title="Rename" class={action}>
+                    {icon!("edit", classes!("fill-gray-400", "w-4", "h-4"))}
+                </button>
+                <button onclick={on_duplicate} title="Duplicate" class={action}>
+                    {icon!("content_copy", classes!("fill-gray-400", "w-4", "h-4"))}
+                </button>
+                <button onclick={on_delete} title="Delete" class={action}>
+                    {icon!("delete", classes!("fill-gray-400", "w-4", "h-4"))}
+                </button>
+            </li>
+        }
+    };
+
+    let button = "p-2 text-sm bg-gray-700 hover:bg-gray-900 rounded-md shadow-lg text-gray-200 cursor-pointer flex items-center gap-1";
+    html! {
+        <aside class="w-64 shrink-0 bg-gray-800 border-r border-gray-600 flex flex-col gap-3 p-3 overflow-y-auto">
+            <div class="text-gray-400 text-xs font-semibold uppercase tracking-wider">{"Projects"}</div>
+            <div class="flex flex-wrap gap-2">
+                <button onclick={on_new} class={button}>
+                    {icon!("add", classes!("fill-gray-200", "w-4", "h-4"))}
+                    {"New"}
+                </button>
+                if props.current.is_none() {
+                    <button onclick={on_save_current} title="Keep the editor's contents as a project" class={button}>
+                        {"Save current"}
+                    </button>
+                }
+            </div>
+            if workspaces.is_empty() {
+                <div class="text-sm text-gray-500">{"No projects saved in this browser yet"}</div>
+            }
+            <ul class="flex flex-col gap-1">
+                for workspace in workspaces.iter() {
+                    {item(workspace)}
+                }
+            </ul>
+            if let Some(e) = &*error {
+                <div role="alert" class="text-sm text-red-400">{e.to_string()}</div>
+            }
+            <div class="mt-auto flex gap-2">
+                <button onclick={on_export} disabled={workspaces.is_empty()} title="Download every project as JSON" class={button}>
+                    {icon!("download", classes!("fill-gray-200", "w-4", "h-4"))}
+                    {"Export"}
+                </button>
+                <label title="Add the projects of an exported JSON file" class={button}>
+                    {icon!("upload", classes!("fill-gray-200", "w-4", "h-4"))}
+                    {"Import"}
+                    <input type="file" accept="application/json,.json" onchange={on_import} class="hidden" />
+                </label>
+            </div>
+        </aside>
+    }
+}
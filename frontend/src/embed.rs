@@ -0,0 +1,218 @@
+//! Compact editor and output for embedding the playground in other pages, served at `/embed`.
+//!
+//! The host page can drive it with `postMessage`:
+//! - `{ "type": "yew-playground:set-code", "code": "..." }` replaces the code,
+//! - `{ "type": "yew-playground:run" }` runs it.
+//!
+//! The embed posts `{ "type": "yew-playground:ready" }` to its parent once the code is loaded
+//! and `{ "type": "yew-playground:result", "status": ... }` after every run, see [`RunOutcome`].
+
+use std::rc::Rc;
+
+use gloo::events::EventListener;
+use serde::{Deserialize, Serialize};
+use split_yew::{Direction, Split};
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yew::suspense::Suspense;
+use yew_router::hooks::use_location;
+
+use crate::api::share::Share;
+use crate::components::editor::Editor;
+use crate::components::output::{CompileTimer, OutputContainer, RunOutcome, RunSettings};
+use crate::utils::project::{ProjectFile, ProjectFiles};
+use crate::{ActionButtonState, ActionButtonStateContext, icon};
+
+/// Options taken from the query, next to the `shared`, `code` and `version` of the main page.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+struct EmbedOptions {
+    readonly: bool,
+    autorun: bool,
+    /// Height of the embed in pixels, it fills its frame otherwise.
+    height: Option<u32>,
+    version: Option<String>,
+}
+
+/// Messages the host page sends.
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum HostMessage {
+    #[serde(rename = "yew-playground:set-code")]
+    SetCode { code: String },
+    #[serde(rename = "yew-playground:run")]
+    Run,
+}
+
+/// Messages sent to the host page.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum EmbedMessage<'a> {
+    #[serde(rename = "yew-playground:ready")]
+    Ready,
+    #[serde(rename = "yew-playground:result")]
+    Result(&'a RunOutcome),
+}
+
+fn post_to_host(message: &EmbedMessage) {
+    let Ok(Some(parent)) = gloo::utils::window().parent() else {
+        return;
+    };
+    let json = serde_json::to_string(message).expect("messages serialize");
+    let data = js_sys::JSON::parse(&json).expect("serde_json emits valid JSON");
+    // runs are not secret, any host may listen
+    if let Err(e) = parent.post_message(&data, "*") {
+        tracing::warn!(?e, "failed to post message to the host page");
+    }
+}
+
+/// The message `event` carries, if it is one. Only the host page may send them: the running app
+/// lives in a frame of its own and must not be able to replace the code or trigger runs.
+fn parse_host_message(event: &web_sys::MessageEvent) -> Option<HostMessage> {
+    let source = event.source()?;
+    let parent = gloo::utils::window().parent().ok()??;
+    if !js_sys::Object::is(&source, &parent) {
+        return None;
+    }
+    let json = js_sys::JSON::stringify(&event.data()).ok()?;
+    serde_json::from_str(&String::from(json)).ok()
+}
+
+#[component]
+pub fn Embed() -> Html {
+    let options = use_location()
+        .and_then(|location| location.query::<EmbedOptions>().ok())
+        .unwrap_or_default();
+    let editor_contents = use_mut_ref(ProjectFiles::default);
+    let data = use_state(|| None::<(Rc<ProjectFiles>, RunSettings)>);
+    let run_count = use_state(|| 0u32);
+    let version = use_state(|| AttrValue::from(options.version.as_deref().unwrap_or("stable")));
+    let opt_level = use_state(|| AttrValue::from("release"));
+    let tailwind = use_state(|| false);
+    let snippet_code = use_state(|| None::<AttrValue>);
+    let action_button_state = use_context::<ActionButtonStateContext>().unwrap();
+    let autoran = use_mut_ref(|| false);
+
+    let run = {
+        let editor_contents = editor_contents.clone();
+        let data = data.clone();
+        let run_count = run_count.clone();
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        let action_button_state = action_button_state.clone();
+        Callback::from(move |_: ()| {
+            data.set(Some((
+                Rc::new(editor_contents.as_ref().borrow().clone()),
+                RunSettings {
+                    version: (*version).clone(),
+                    opt_level: (*opt_level).clone(),
+                    tailwind: *tailwind,
+                },
+            )));
+            run_count.set(*run_count + 1);
+            action_button_state.dispatch(ActionButtonState::Disabled);
+        })
+    };
+
+    {
+        let run = run.clone();
+        let snippet_code = snippet_code.clone();
+        use_effect_with((), move |_| {
+            let listener = EventListener::new(&gloo::utils::window(), "message", move |event| {
+                let Some(event) = event.dyn_ref::<web_sys::MessageEvent>() else {
+                    return;
+                };
+                match parse_host_message(event) {
+                    Some(HostMessage::SetCode { code }) => {
+                        snippet_code.set(Some(AttrValue::from(code)))
+                    }
+                    Some(HostMessage::Run) => run.emit(()),
+                    None => {}
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
+    let onready = {
+        let run = run.clone();
+        let autorun = options.autorun;
+        move |_| {
+            post_to_host(&EmbedMessage::Ready);
+            if autorun && !autoran.replace(true) {
+                run.emit(());
+            }
+        }
+    };
+
+    let onload = {
+        let version = version.clone();
+        let opt_level = opt_level.clone();
+        let tailwind = tailwind.clone();
+        let query_version = options.version.clone();
+        move |share: Share| {
+            version.set(match &query_version {
+                Some(version) => AttrValue::from(version.clone()),
+                None => AttrValue::from(share.version.name()),
+            });
+            opt_level.set(AttrValue::from(share.settings.opt.name()));
+            tailwind.set(share.settings.tailwind);
+        }
+    };
+
+    let oninput = move |(file, v): (ProjectFile, String)| {
+        *editor_contents.as_ref().borrow_mut().get_mut(file) = v;
+    };
+
+    let on_finish = |outcome: RunOutcome| post_to_host(&EmbedMessage::Result(&outcome));
+
+    // the same project in the full playground
+    let playground_url = {
+        let location = gloo::utils::window().location();
+        format!(
+            "/{}{}",
+            location.search().unwrap_or_default(),
+            location.hash().unwrap_or_default()
+        )
+    };
+    let style = options.height.map(|height| format!("height: {height}px"));
+
+    html! {
+        <div class="flex flex-col h-screen" {style}>
+            <Split sizes={vec![50.0, 50.0]} direction={Direction::Horizontal} class="flex flex-grow overflow-hidden">
+                <div class="relative">
+                    <Suspense fallback={{html! {"loading..."}}}>
+                        <Editor {oninput} {onload} {onready} readonly={options.readonly} snippet_code={(*snippet_code).clone()} />
+                    </Suspense>
+                    <div class="absolute bottom-3 right-3 z-10 flex items-center gap-2">
+                        <a href={playground_url} target="_blank" class="text-xs text-gray-400 hover:text-gray-200 underline">
+                            {"Open in playground"}
+                        </a>
+                        <button
+                            onclick={run.reform(|_: MouseEvent| ())}
+                            disabled={action_button_state.disabled()}
+                            title="Run"
+                            class="p-2 bg-gray-800 hover:bg-gray-900 rounded-md shadow-lg cursor-pointer disabled:cursor-not-allowed disabled:bg-gray-700"
+                        >
+                            {icon!("play_arrow", classes!("fill-gray-200", "w-4", "h-4"))}
+                        </button>
+                    </div>
+                </div>
+                <div class="min-w-0 bg-gray-600">
+                    if let Some((ref files, ref settings)) = *data {
+                        <Suspense fallback={html! { <CompileTimer /> }}>
+                            <OutputContainer
+                                {files}
+                                settings={settings.clone()}
+                                on_retry={run.clone()}
+                                {on_finish}
+                                key={*run_count}
+                            />
+                        </Suspense>
+                    }
+                </div>
+            </Split>
+        </div>
+    }
+}
@@ -1,6 +1,7 @@
 mod api;
 mod app;
 mod components;
+mod embed;
 mod macros;
 pub mod snippets;
 mod utils;
@@ -10,6 +11,7 @@ use tracing_subscriber::prelude::*;
 use tracing_web::{MakeConsoleWriter, performance_layer};
 
 use app::App;
+use embed::Embed;
 use std::rc::Rc;
 use yew::prelude::*;
 use yew_router::prelude::*;
@@ -41,6 +43,22 @@ impl Reducible for ActionButtonStateReducible {
 
 pub type ActionButtonStateContext = UseReducerHandle<ActionButtonStateReducible>;
 
+#[derive(Routable, Clone, PartialEq)]
+enum Route {
+    #[at("/embed")]
+    Embed,
+    #[not_found]
+    #[at("/")]
+    Playground,
+}
+
+fn switch(route: Route) -> Html {
+    match route {
+        Route::Embed => html! { <Embed /> },
+        Route::Playground => html! { <App /> },
+    }
+}
+
 #[component]
 fn Root() -> Html {
     let msg = use_reducer_eq(|| ActionButtonStateReducible {
@@ -50,7 +68,7 @@ fn Root() -> Html {
     html! {
         <BrowserRouter>
             <ContextProvider<ActionButtonStateContext> context={msg}>
-                <App />
+                <Switch<Route> render={switch} />
             </ContextProvider<ActionButtonStateContext>>
         </BrowserRouter>
     }
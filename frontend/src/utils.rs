@@ -1,5 +1,15 @@
+pub mod drafts;
+pub mod inline;
+pub mod lsp;
+pub mod project;
 pub mod query;
+pub mod share;
+pub mod shortcuts;
+pub mod workspaces;
+pub mod yew_completions;
 
+use gloo::file::{Blob, ObjectUrl};
+use wasm_bindgen::JsCast;
 use yew::Classes;
 use yew::virtual_dom::VNode;
 
@@ -13,3 +23,15 @@ pub fn html_to_element(html: &str, classes: Option<Classes>) -> VNode {
     }
     VNode::VRef(node.into())
 }
+
+/// Has the browser save `bytes` as a download named `file_name`.
+pub fn save_file(bytes: &[u8], mime_type: &str, file_name: &str) {
+    let url = ObjectUrl::from(Blob::new_with_options(bytes, Some(mime_type)));
+    let anchor: web_sys::HtmlAnchorElement = gloo::utils::document()
+        .create_element("a")
+        .expect("can create elements")
+        .unchecked_into();
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+}
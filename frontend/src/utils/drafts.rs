@@ -0,0 +1,55 @@
+//! Unsaved work kept in `localStorage`, so reloading the page does not lose it.
+
+use gloo::storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::project::ProjectFiles;
+
+/// Drafts kept per template version, the oldest are dropped first.
+const MAX_DRAFTS: usize = 10;
+
+fn key(version: &str) -> String {
+    format!("yew-playground:drafts:{version}")
+}
+
+/// The files of one editing session, as last saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    /// When the session started, in milliseconds since the epoch. Saves of the same session
+    /// replace each other.
+    pub session: f64,
+    pub saved_at: f64,
+    pub files: ProjectFiles,
+}
+
+impl Draft {
+    /// When the draft was saved, in the user's locale.
+    pub fn saved_at_label(&self) -> String {
+        js_sys::Date::new(&self.saved_at.into())
+            .to_locale_string("default", &wasm_bindgen::JsValue::UNDEFINED)
+            .into()
+    }
+}
+
+/// Drafts for `version`, newest first.
+pub fn load(version: &str) -> Vec<Draft> {
+    LocalStorage::get(key(version)).unwrap_or_default()
+}
+
+pub fn save(version: &str, session: f64, files: &ProjectFiles) {
+    let mut drafts = load(version);
+    drafts.retain(|it| it.session != session);
+    drafts.insert(
+        0,
+        Draft {
+            session,
+            saved_at: js_sys::Date::now(),
+            files: files.clone(),
+        },
+    );
+    drafts.truncate(MAX_DRAFTS);
+    // storage can be full or disabled, autosave is best effort
+    if let Err(e) = LocalStorage::set(key(version), drafts) {
+        tracing::warn!(%e, "failed to save draft");
+    }
+}
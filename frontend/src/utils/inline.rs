@@ -0,0 +1,120 @@
+//! Projects encoded into the URL fragment, so small programs can be shared without storing
+//! anything on the backend. The fragment is `#share=` followed by the project's JSON,
+//! deflated and base64url encoded.
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec_with_limit;
+
+use crate::api::RequestError;
+use crate::api::share::Share;
+
+const PREFIX: &str = "share=";
+/// Longer fragments make links too unwieldy to paste around, those projects are shared through
+/// the backend instead.
+pub const MAX_FRAGMENT_LEN: usize = 2000;
+/// Bounds what a crafted link can make the browser inflate.
+const MAX_DECODED_LEN: usize = 256 * 1024;
+
+/// Encodes `share` as a URL fragment (without the `#`), or `None` if it is too large.
+pub fn encode(share: &Share) -> Option<String> {
+    // the template's dependencies are known from its version, no need to spend URL space
+    let share = Share {
+        dependencies: Default::default(),
+        created_at: None,
+        ..share.clone()
+    };
+    let json = serde_json::to_vec(&share).expect("shares serialize");
+    let encoded = URL_SAFE_NO_PAD.encode(compress_to_vec(&json, 9));
+    let fragment = format!("{PREFIX}{encoded}");
+    (fragment.len() <= MAX_FRAGMENT_LEN).then_some(fragment)
+}
+
+/// Decodes a fragment made by [`encode`]. Returns `None` if it is not a shared project at all.
+pub fn decode(fragment: &str) -> Option<Result<Share, RequestError>> {
+    let encoded = fragment.strip_prefix('#').unwrap_or(fragment);
+    let encoded = encoded.strip_prefix(PREFIX)?;
+    Some(decode_share(encoded).map_err(RequestError::Corrupt))
+}
+
+fn decode_share(encoded: &str) -> Result<Share, String> {
+    let compressed = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| e.to_string())?;
+    let json =
+        decompress_to_vec_with_limit(&compressed, MAX_DECODED_LEN).map_err(|e| e.to_string())?;
+    serde_json::from_slice(&json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::share::ShareSettings;
+    use crate::api::{OptLevel, YewVersion};
+    use crate::utils::project::ProjectFiles;
+
+    fn share(code: String) -> Share {
+        Share {
+            version: YewVersion::Next,
+            title: "counter".to_string(),
+            files: ProjectFiles::with_code(code),
+            settings: ShareSettings {
+                opt: OptLevel::Size,
+                tailwind: true,
+            },
+            parent: Some("aZ09-_aZ09-_".to_string()),
+            ..Share::default()
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let share = share("fn main() {}".to_string());
+        let fragment = encode(&share).unwrap();
+        assert!(fragment.starts_with(PREFIX));
+        assert_eq!(decode(&format!("#{fragment}")), Some(Ok(share.clone())));
+        assert_eq!(decode(&fragment), Some(Ok(share)));
+    }
+
+    #[test]
+    fn leaves_out_what_the_backend_knows() {
+        let mut share = share("fn main() {}".to_string());
+        share.created_at = Some(1);
+        share
+            .dependencies
+            .insert("yew".to_string(), "0.23".to_string());
+        let decoded = decode(&encode(&share).unwrap()).unwrap().unwrap();
+        assert_eq!(decoded.created_at, None);
+        assert!(decoded.dependencies.is_empty());
+    }
+
+    #[test]
+    fn large_projects_do_not_fit() {
+        // deflate cannot shrink pseudo-random code much
+        let mut state = 1u32;
+        let code = (0..4000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                char::from(b'!' + (state >> 16) as u8 % 90)
+            })
+            .collect();
+        assert_eq!(encode(&share(code)), None);
+    }
+
+    #[test]
+    fn rejects_corrupt_links() {
+        assert_eq!(decode("other=1"), None);
+        assert_eq!(decode(""), None);
+        let corrupt =
+            |fragment: &str| matches!(decode(fragment), Some(Err(RequestError::Corrupt(_))));
+        // not base64url
+        assert!(corrupt("share=!!!"));
+        // not deflated
+        assert!(corrupt("share=AAAA"));
+        // not a share
+        let not_json = URL_SAFE_NO_PAD.encode(compress_to_vec(b"[1, 2", 9));
+        assert!(corrupt(&format!("share={not_json}")));
+        // cut off when pasted
+        let fragment = encode(&share("fn main() {}".to_string())).unwrap();
+        assert!(corrupt(&fragment[..fragment.len() / 2]));
+    }
+}
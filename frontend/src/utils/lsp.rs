@@ -0,0 +1,524 @@
+//! Connects the editor's code tab to a rust-analyzer session: the model is kept in sync with the
+//! server, which answers Monaco's completion, hover and go-to-definition requests and reports
+//! diagnostics as markers.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use js_sys::{Object, Promise, Reflect};
+use monaco::api::{DisposableClosure, TextModel};
+use monaco::sys::editor::{IModelContentChangedEvent, ITextModel};
+use monaco::sys::{IDisposable, languages};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use yew::Callback;
+
+use crate::api::lsp::{LspClient, LspError, MAIN_URI};
+
+/// Owner of the markers set from diagnostics, so they are replaced and cleared as a group.
+const MARKER_OWNER: &str = "rust-analyzer";
+
+type Provider = Closure<dyn Fn(JsValue, JsValue) -> Promise>;
+
+/// A live session for one code model. Dropping it unregisters everything and ends the session.
+pub struct LspBinding {
+    model: TextModel,
+    _client: LspClient,
+    _on_change: DisposableClosure<dyn FnMut(IModelContentChangedEvent)>,
+    providers: Vec<IDisposable>,
+    _closures: Vec<Provider>,
+}
+
+impl LspBinding {
+    /// Starts a session against the `version` template for `model`.
+    pub async fn connect(version: &str, model: TextModel) -> Result<Self, LspError> {
+        let on_diagnostics = {
+            let model = model.clone();
+            Callback::from(move |diagnostics: Vec<Value>| {
+                let markers: Vec<_> = diagnostics.iter().filter_map(Marker::from_lsp).collect();
+                set_markers(&model, &to_js(&markers));
+            })
+        };
+        let client = LspClient::connect(version, on_diagnostics).await?;
+
+        client.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": {
+                    "uri": MAIN_URI,
+                    "languageId": "rust",
+                    "version": 0,
+                    "text": model.get_value(),
+                },
+            }),
+        );
+        let on_change = {
+            let client = client.clone();
+            let model2 = model.clone();
+            let version = Cell::new(0);
+            model.on_did_change_content(move |_| {
+                version.set(version.get() + 1);
+                client.notify(
+                    "textDocument/didChange",
+                    json!({
+                        "textDocument": { "uri": MAIN_URI, "version": version.get() },
+                        "contentChanges": [{ "text": model2.get_value() }],
+                    }),
+                );
+            })
+        };
+
+        let sys_model: &ITextModel = model.as_ref();
+        let model_js = Rc::new(JsValue::clone(sys_model));
+        let completion = provider(&client, &model_js, |client, _, position| async move {
+            let result = client
+                .request("textDocument/completion", position_params(&position))
+                .await?;
+            let suggestions: Vec<_> = match serde_json::from_value(result) {
+                Ok(CompletionResponse::List { items }) | Ok(CompletionResponse::Items(items)) => {
+                    items.into_iter().map(Suggestion::from_lsp).collect()
+                }
+                Err(_) => Vec::new(),
+            };
+            Ok(to_js(&json!({ "suggestions": suggestions })))
+        });
+        let hover = provider(&client, &model_js, |client, _, position| async move {
+            let result = client
+                .request("textDocument/hover", position_params(&position))
+                .await?;
+            let Ok(Some(hover)) = serde_json::from_value::<Option<Hover>>(result) else {
+                return Ok(JsValue::NULL);
+            };
+            Ok(to_js(&json!({
+                "contents": hover.contents.into_markdown(),
+                "range": hover.range.map(MonacoRange::from),
+            })))
+        });
+        let definition = provider(&client, &model_js, |client, model, position| async move {
+            let result = client
+                .request("textDocument/definition", position_params(&position))
+                .await?;
+            let ranges = serde_json::from_value::<Option<Definition>>(result)
+                .ok()
+                .flatten()
+                .map(Definition::into_ranges)
+                .unwrap_or_default();
+            // only the code tab can be shown, definitions in Yew or std are left out
+            let uri = Reflect::get(&model, &"uri".into()).unwrap_or(JsValue::UNDEFINED);
+            let locations = js_sys::Array::new();
+            for range in ranges {
+                let location = Object::new();
+                let _ = Reflect::set(&location, &"uri".into(), &uri);
+                let _ = Reflect::set(
+                    &location,
+                    &"range".into(),
+                    &to_js(&MonacoRange::from(range)),
+                );
+                locations.push(&location);
+            }
+            Ok(locations.into())
+        });
+
+        let completion_provider = Object::new();
+        let _ = Reflect::set(
+            &completion_provider,
+            &"triggerCharacters".into(),
+            &to_js(&[".", ":", "<"]),
+        );
+        let _ = Reflect::set(
+            &completion_provider,
+            &"provideCompletionItems".into(),
+            completion.as_ref(),
+        );
+        let hover_provider = Object::new();
+        let _ = Reflect::set(&hover_provider, &"provideHover".into(), hover.as_ref());
+        let definition_provider = Object::new();
+        let _ = Reflect::set(
+            &definition_provider,
+            &"provideDefinition".into(),
+            definition.as_ref(),
+        );
+
+        let providers = vec![
+            languages::register_completion_item_provider(
+                "rust",
+                completion_provider.unchecked_ref(),
+            ),
+            languages::register_hover_provider("rust", hover_provider.unchecked_ref()),
+            languages::register_definition_provider("rust", definition_provider.unchecked_ref()),
+        ];
+
+        Ok(Self {
+            model,
+            _client: client,
+            _on_change: on_change,
+            providers,
+            _closures: vec![completion, hover, definition],
+        })
+    }
+}
+
+impl Drop for LspBinding {
+    fn drop(&mut self) {
+        for provider in &self.providers {
+            provider.dispose();
+        }
+        set_markers(&self.model, &js_sys::Array::new());
+    }
+}
+
+/// A Monaco provider function answering with `answer`, for requests about `model` only. Other
+/// Rust models, such as a diff view, get no answer.
+fn provider<F, Fut>(client: &LspClient, model: &Rc<JsValue>, answer: F) -> Provider
+where
+    F: Fn(LspClient, JsValue, Value) -> Fut + 'static,
+    Fut: Future<Output = Result<JsValue, LspError>> + 'static,
+{
+    let client = client.clone();
+    let model = model.clone();
+    Closure::new(move |requested: JsValue, position: JsValue| {
+        if !Object::is(&requested, &model) {
+            return Promise::resolve(&JsValue::NULL);
+        }
+        let position = Position::from_monaco(&position);
+        let answer = answer(client.clone(), requested, json!(position));
+        wasm_bindgen_futures::future_to_promise(async move {
+            // a failed request only means there is nothing to offer
+            Ok(answer.await.unwrap_or(JsValue::NULL))
+        })
+    })
+}
+
+fn position_params(position: &Value) -> Value {
+    json!({ "textDocument": { "uri": MAIN_URI }, "position": position })
+}
+
+fn set_markers(model: &TextModel, markers: &JsValue) {
+    monaco::sys::editor::set_model_markers(model.as_ref(), MARKER_OWNER, markers.unchecked_ref());
+}
+
+/// Plain data for Monaco, which takes JSON-shaped objects.
+pub fn to_js(value: &impl Serialize) -> JsValue {
+    let json = serde_json::to_string(value).expect("the value serializes to JSON");
+    js_sys::JSON::parse(&json).expect("serde_json writes valid JSON")
+}
+
+/// A zero-based LSP position. Both count UTF-16 code units, so only the base differs from
+/// Monaco's.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Position {
+    line: u32,
+    character: u32,
+}
+
+impl Position {
+    fn from_monaco(position: &JsValue) -> Self {
+        let get = |key: &str| {
+            Reflect::get(position, &key.into())
+                .ok()
+                .and_then(|it| it.as_f64())
+                .unwrap_or(1.0) as u32
+        };
+        Self {
+            line: get("lineNumber").saturating_sub(1),
+            character: get("column").saturating_sub(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct Range {
+    start: Position,
+    end: Position,
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonacoRange {
+    pub start_line_number: u32,
+    pub start_column: u32,
+    pub end_line_number: u32,
+    pub end_column: u32,
+}
+
+impl From<Range> for MonacoRange {
+    fn from(range: Range) -> Self {
+        Self {
+            start_line_number: range.start.line + 1,
+            start_column: range.start.character + 1,
+            end_line_number: range.end.line + 1,
+            end_column: range.end.character + 1,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Marker {
+    severity: u8,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(flatten)]
+    range: MonacoRange,
+}
+
+impl Marker {
+    fn from_lsp(diagnostic: &Value) -> Option<Self> {
+        #[derive(Deserialize)]
+        struct Diagnostic {
+            range: Range,
+            severity: Option<u8>,
+            message: String,
+            source: Option<String>,
+        }
+        let diagnostic = Diagnostic::deserialize(diagnostic).ok()?;
+        // Monaco's MarkerSeverity, which counts the other way around
+        let severity = match diagnostic.severity {
+            Some(1) | None => 8,
+            Some(2) => 4,
+            Some(3) => 2,
+            Some(_) => 1,
+        };
+        Some(Self {
+            severity,
+            message: diagnostic.message,
+            source: diagnostic.source,
+            range: diagnostic.range.into(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CompletionResponse {
+    List { items: Vec<CompletionItem> },
+    Items(Vec<CompletionItem>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionItem {
+    label: String,
+    kind: Option<u8>,
+    detail: Option<String>,
+    documentation: Option<Markup>,
+    sort_text: Option<String>,
+    filter_text: Option<String>,
+    insert_text: Option<String>,
+    insert_text_format: Option<u8>,
+    text_edit: Option<TextEdit>,
+    #[serde(default)]
+    additional_text_edits: Vec<TextEdit>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextEdit {
+    Replace {
+        range: Range,
+        #[serde(rename = "newText")]
+        new_text: String,
+    },
+    InsertReplace {
+        replace: Range,
+        #[serde(rename = "newText")]
+        new_text: String,
+    },
+}
+
+impl TextEdit {
+    fn into_parts(self) -> (Range, String) {
+        match self {
+            TextEdit::Replace { range, new_text } => (range, new_text),
+            TextEdit::InsertReplace { replace, new_text } => (replace, new_text),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MonacoTextEdit {
+    range: MonacoRange,
+    text: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Suggestion {
+    label: String,
+    kind: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    documentation: Option<MarkdownString>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filter_text: Option<String>,
+    insert_text: String,
+    /// `CompletionItemInsertTextRule.InsertAsSnippet` for snippets with tab stops.
+    insert_text_rules: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range: Option<MonacoRange>,
+    additional_text_edits: Vec<MonacoTextEdit>,
+}
+
+impl Suggestion {
+    fn from_lsp(item: CompletionItem) -> Self {
+        let (range, insert_text) = match item.text_edit.map(TextEdit::into_parts) {
+            Some((range, text)) => (Some(range.into()), text),
+            None => (None, item.insert_text.unwrap_or_else(|| item.label.clone())),
+        };
+        Self {
+            kind: completion_kind(item.kind.unwrap_or(1)),
+            detail: item.detail,
+            documentation: item.documentation.map(Markup::into_markdown),
+            sort_text: item.sort_text,
+            filter_text: item.filter_text,
+            insert_text,
+            insert_text_rules: if item.insert_text_format == Some(2) {
+                4
+            } else {
+                0
+            },
+            range,
+            additional_text_edits: item
+                .additional_text_edits
+                .into_iter()
+                .map(|edit| {
+                    let (range, text) = edit.into_parts();
+                    MonacoTextEdit {
+                        range: range.into(),
+                        text,
+                    }
+                })
+                .collect(),
+            label: item.label,
+        }
+    }
+}
+
+/// Maps an LSP `CompletionItemKind` to Monaco's, which numbers them differently.
+fn completion_kind(kind: u8) -> u8 {
+    match kind {
+        2 => 0,   // Method
+        3 => 1,   // Function
+        4 => 2,   // Constructor
+        5 => 3,   // Field
+        6 => 4,   // Variable
+        7 => 5,   // Class
+        8 => 7,   // Interface
+        9 => 8,   // Module
+        10 => 9,  // Property
+        11 => 12, // Unit
+        12 => 13, // Value
+        13 => 15, // Enum
+        14 => 17, // Keyword
+        15 => 27, // Snippet
+        16 => 19, // Color
+        17 => 20, // File
+        18 => 21, // Reference
+        19 => 23, // Folder
+        20 => 16, // EnumMember
+        21 => 14, // Constant
+        22 => 6,  // Struct
+        23 => 10, // Event
+        24 => 11, // Operator
+        25 => 24, // TypeParameter
+        _ => 18,  // Text
+    }
+}
+
+#[derive(Serialize)]
+pub struct MarkdownString {
+    pub value: String,
+}
+
+/// Documentation as LSP sends it: markup, a plain string, or a code block.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Markup {
+    Plain(String),
+    Content { kind: String, value: String },
+    Code { language: String, value: String },
+}
+
+impl Markup {
+    fn into_markdown(self) -> MarkdownString {
+        let value = match self {
+            Markup::Plain(value) => value,
+            Markup::Content { kind, value } if kind == "markdown" => value,
+            Markup::Content { value, .. } => format!("```text\n{value}\n```"),
+            Markup::Code { language, value } => format!("```{language}\n{value}\n```"),
+        };
+        MarkdownString { value }
+    }
+}
+
+#[derive(Deserialize)]
+struct Hover {
+    contents: HoverContents,
+    range: Option<Range>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum HoverContents {
+    Many(Vec<Markup>),
+    One(Markup),
+}
+
+impl HoverContents {
+    fn into_markdown(self) -> Vec<MarkdownString> {
+        match self {
+            HoverContents::Many(contents) => {
+                contents.into_iter().map(Markup::into_markdown).collect()
+            }
+            HoverContents::One(markup) => vec![markup.into_markdown()],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Definition {
+    One(Location),
+    Many(Vec<Location>),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Location {
+    Link {
+        #[serde(rename = "targetUri")]
+        target_uri: String,
+        #[serde(rename = "targetSelectionRange")]
+        target_selection_range: Range,
+    },
+    Plain {
+        uri: String,
+        range: Range,
+    },
+}
+
+impl Definition {
+    /// The ranges in the code tab this points to.
+    fn into_ranges(self) -> Vec<Range> {
+        let locations = match self {
+            Definition::One(location) => vec![location],
+            Definition::Many(locations) => locations,
+        };
+        locations
+            .into_iter()
+            .filter_map(|location| match location {
+                Location::Link {
+                    target_uri,
+                    target_selection_range,
+                } => (target_uri == MAIN_URI).then_some(target_selection_range),
+                Location::Plain { uri, range } => (uri == MAIN_URI).then_some(range),
+            })
+            .collect()
+    }
+}
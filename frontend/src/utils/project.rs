@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+
+/// The files of a playground project. `code` is the app's `main.rs`, the others are merged
+/// into the `index.html` the app is served with.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectFiles {
+    pub code: String,
+    #[serde(default)]
+    pub head: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub css: String,
+}
+
+impl ProjectFiles {
+    pub fn with_code(code: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            ..Self::default()
+        }
+    }
+
+    pub fn get(&self, file: ProjectFile) -> &str {
+        match file {
+            ProjectFile::Code => &self.code,
+            ProjectFile::Head => &self.head,
+            ProjectFile::Body => &self.body,
+            ProjectFile::Css => &self.css,
+        }
+    }
+
+    pub fn get_mut(&mut self, file: ProjectFile) -> &mut String {
+        match file {
+            ProjectFile::Code => &mut self.code,
+            ProjectFile::Head => &mut self.head,
+            ProjectFile::Body => &mut self.body,
+            ProjectFile::Css => &mut self.css,
+        }
+    }
+}
+
+/// One of the editor tabs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectFile {
+    Code,
+    Head,
+    Body,
+    Css,
+}
+
+impl ProjectFile {
+    pub const ALL: [ProjectFile; 4] = [
+        ProjectFile::Code,
+        ProjectFile::Body,
+        ProjectFile::Head,
+        ProjectFile::Css,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ProjectFile::Code => "main.rs",
+            ProjectFile::Head => "head.html",
+            ProjectFile::Body => "body.html",
+            ProjectFile::Css => "style.css",
+        }
+    }
+
+    /// Monaco language id.
+    pub fn language(self) -> &'static str {
+        match self {
+            ProjectFile::Code => "rust",
+            ProjectFile::Head | ProjectFile::Body => "html",
+            ProjectFile::Css => "css",
+        }
+    }
+}
@@ -2,11 +2,17 @@ use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 use yew_router::hooks::use_location;
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Query {
     pub shared: Option<String>,
     pub code: Option<String>,
     pub version: Option<String>,
+    /// Id of a GitHub gist to load, fetched through the backend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gist: Option<String>,
+    /// URL of a raw file to load, fetched through the backend.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 #[hook]
@@ -14,3 +20,11 @@ pub fn use_query() -> Option<Query> {
     let location = use_location()?;
     location.query::<Query>().ok()
 }
+
+/// The URL fragment, including the leading `#` if there is one.
+#[hook]
+pub fn use_fragment() -> String {
+    use_location()
+        .map(|location| location.hash().to_string())
+        .unwrap_or_default()
+}
@@ -0,0 +1,122 @@
+//! Sharing the project: as an inline link when it is small enough, otherwise stored by the
+//! backend, and remembering the share it was opened from so forks keep their parent.
+
+use crate::api::RequestError;
+use crate::api::share::Share;
+use crate::utils::inline;
+use crate::utils::query::Query;
+use crate::{ActionButtonState, ActionButtonStateContext};
+use gloo::history::{BrowserHistory, History};
+use yew::prelude::*;
+
+pub struct UseShareHandle {
+    /// Permalink path of the project that was just shared, and its inline link if it fits in one.
+    pub link: Option<(AttrValue, Option<AttrValue>)>,
+    /// Id of the share the opened one was forked from.
+    pub forked_from: Option<AttrValue>,
+    /// Shares the project, giving the link of the share it was opened from if it is unchanged.
+    pub share: Callback<Share>,
+    /// Records the share the editor was opened with, and its id if it is stored.
+    pub opened: Callback<(Option<AttrValue>, Share)>,
+    /// Forgets the opened share, when something else is opened.
+    pub forget: Callback<()>,
+    /// Closes the dialog with the link.
+    pub close: Callback<()>,
+}
+
+#[hook]
+pub fn use_share(on_error: Callback<RequestError>) -> UseShareHandle {
+    let action_button_state = use_context::<ActionButtonStateContext>().unwrap();
+    // the share the editor was opened with, as it was opened
+    let loaded = use_mut_ref(|| None::<(AttrValue, Share)>);
+    let forked_from = use_state(|| None::<AttrValue>);
+    let link = use_state(|| None::<(AttrValue, Option<AttrValue>)>);
+
+    let share = {
+        let loaded = loaded.clone();
+        let link = link.clone();
+        Callback::from(move |mut share: Share| {
+            let action_button_state = action_button_state.clone();
+            let on_error = on_error.clone();
+            let link = link.clone();
+
+            // sharing an unchanged share again gives its own link rather than a copy of it
+            if let Some((id, loaded)) = &*loaded.borrow() {
+                if loaded.same_project(&share) {
+                    let inline_path =
+                        inline::encode(&share).map(|it| AttrValue::from(format!("/#{it}")));
+                    link.set(Some((
+                        AttrValue::from(format!("/?shared={id}")),
+                        inline_path,
+                    )));
+                    return;
+                }
+                share.parent = Some(id.to_string());
+            }
+            let inline_path = inline::encode(&share).map(|it| AttrValue::from(format!("/#{it}")));
+            // small projects are shared without the backend, the link carries all of it, but
+            // forks are stored so they keep their parent
+            if let Some(path) = &inline_path
+                && share.parent.is_none()
+            {
+                BrowserHistory::new().push(path.to_string());
+                link.set(Some((path.clone(), inline_path.clone())));
+                return;
+            }
+
+            yew::platform::spawn_local(async move {
+                action_button_state.dispatch(ActionButtonState::Disabled);
+                match crate::api::share::create(&share).await {
+                    Ok(id) => {
+                        // the share knows its version and settings
+                        let query = Query {
+                            shared: Some(id.clone()),
+                            ..Query::default()
+                        };
+                        // the dialog has the link either way
+                        if let Err(e) = BrowserHistory::new().push_with_query("/", query) {
+                            tracing::warn!(%e, "failed to show the share in the address bar");
+                        }
+                        link.set(Some((
+                            AttrValue::from(format!("/?shared={id}")),
+                            inline_path,
+                        )));
+                    }
+                    Err(e) => on_error.emit(e),
+                }
+                action_button_state.dispatch(ActionButtonState::Enabled);
+            })
+        })
+    };
+
+    let opened = {
+        let loaded = loaded.clone();
+        let forked_from = forked_from.clone();
+        Callback::from(move |(id, share): (Option<AttrValue>, Share)| {
+            forked_from.set(share.parent.clone().map(AttrValue::from));
+            *loaded.borrow_mut() = id.map(|id| (id, share));
+        })
+    };
+
+    let forget = {
+        let forked_from = forked_from.clone();
+        Callback::from(move |_| {
+            *loaded.borrow_mut() = None;
+            forked_from.set(None);
+        })
+    };
+
+    let close = {
+        let link = link.clone();
+        Callback::from(move |_| link.set(None))
+    };
+
+    UseShareHandle {
+        link: (*link).clone(),
+        forked_from: (*forked_from).clone(),
+        share,
+        opened,
+        forget,
+        close,
+    }
+}
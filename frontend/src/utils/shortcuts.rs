@@ -0,0 +1,175 @@
+//! Keyboard shortcuts, handled as Monaco actions while the editor has focus and by a window
+//! listener everywhere else.
+
+use crate::ActionButtonStateContext;
+use gloo::events::{EventListener, EventListenerOptions};
+use monaco::sys::{KeyCode, KeyMod};
+use wasm_bindgen::JsCast;
+use web_sys::KeyboardEvent;
+use yew::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortcut {
+    Run,
+    /// Saves the open workspace, or shares the project when there is none.
+    Save,
+    Format,
+    ToggleVersion,
+    Help,
+}
+
+impl Shortcut {
+    pub const ALL: [Shortcut; 5] = [
+        Shortcut::Run,
+        Shortcut::Save,
+        Shortcut::Format,
+        Shortcut::ToggleVersion,
+        Shortcut::Help,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Shortcut::Run => "Run",
+            Shortcut::Save => "Save the project, or share it",
+            Shortcut::Format => "Format the code",
+            Shortcut::ToggleVersion => "Switch between Yew stable and next",
+            Shortcut::Help => "Show keyboard shortcuts",
+        }
+    }
+
+    /// Id of the Monaco action.
+    pub fn action_id(self) -> &'static str {
+        match self {
+            Shortcut::Run => "yew-playground.run",
+            Shortcut::Save => "yew-playground.save",
+            Shortcut::Format => "yew-playground.format",
+            Shortcut::ToggleVersion => "yew-playground.toggle-version",
+            Shortcut::Help => "yew-playground.help",
+        }
+    }
+
+    /// The keys to press, spelled for the user's platform.
+    pub fn keys(self) -> String {
+        let ctrl = if is_mac() { "⌘" } else { "Ctrl" };
+        match self {
+            Shortcut::Run => format!("{ctrl} + Enter"),
+            Shortcut::Save => format!("{ctrl} + S"),
+            Shortcut::Format => "Shift + Alt + F".to_string(),
+            Shortcut::ToggleVersion => format!("{ctrl} + Shift + Y"),
+            Shortcut::Help => "?".to_string(),
+        }
+    }
+
+    /// The Monaco keybinding. `?` is left to the window listener, in the editor it is typed.
+    pub fn keybinding(self) -> Option<u32> {
+        let ctrl_cmd = KeyMod::ctrl_cmd() as u32;
+        let shift = KeyMod::shift() as u32;
+        let alt = KeyMod::alt() as u32;
+        match self {
+            Shortcut::Run => Some(ctrl_cmd | KeyCode::Enter.to_value()),
+            Shortcut::Save => Some(ctrl_cmd | KeyCode::KeyS.to_value()),
+            Shortcut::Format => Some(shift | alt | KeyCode::KeyF.to_value()),
+            Shortcut::ToggleVersion => Some(ctrl_cmd | shift | KeyCode::KeyY.to_value()),
+            Shortcut::Help => None,
+        }
+    }
+
+    /// The shortcut `event` triggers, if any. Letters are matched by their physical key, since
+    /// Alt changes the character typed on macOS.
+    pub fn from_event(event: &KeyboardEvent) -> Option<Self> {
+        let ctrl_cmd = event.ctrl_key() || event.meta_key();
+        let code = event.code();
+        match code.as_str() {
+            "Enter" if ctrl_cmd => Some(Shortcut::Run),
+            "KeyS" if ctrl_cmd && !event.shift_key() => Some(Shortcut::Save),
+            "KeyF" if event.shift_key() && event.alt_key() && !ctrl_cmd => Some(Shortcut::Format),
+            "KeyY" if ctrl_cmd && event.shift_key() => Some(Shortcut::ToggleVersion),
+            _ if event.key() == "?" && !ctrl_cmd && !is_editable(event) => Some(Shortcut::Help),
+            _ => None,
+        }
+    }
+}
+
+fn is_mac() -> bool {
+    gloo::utils::window()
+        .navigator()
+        .platform()
+        .is_ok_and(|it| it.starts_with("Mac"))
+}
+
+/// Whether the event is typing into a text field, where `?` is just a character.
+fn is_editable(event: &KeyboardEvent) -> bool {
+    event
+        .target()
+        .and_then(|it| it.dyn_into::<web_sys::HtmlElement>().ok())
+        .is_some_and(|it| {
+            matches!(it.tag_name().as_str(), "INPUT" | "TEXTAREA") || it.is_content_editable()
+        })
+}
+
+/// What the shortcuts do, the callbacks of the buttons they stand for.
+pub struct ShortcutActions {
+    pub run: Callback<()>,
+    pub save: Callback<()>,
+    pub format: Callback<()>,
+    pub toggle_version: Callback<()>,
+}
+
+pub struct UseShortcutsHandle {
+    /// Handles a shortcut, for the editor's actions.
+    pub on_shortcut: Callback<Shortcut>,
+    /// Whether the list of shortcuts is shown.
+    pub help_open: UseStateHandle<bool>,
+}
+
+/// Handles the shortcuts pressed outside the editor, `Escape` closes the list of them.
+#[hook]
+pub fn use_shortcuts(actions: ShortcutActions) -> UseShortcutsHandle {
+    let action_button_state = use_context::<ActionButtonStateContext>().unwrap();
+    let help_open = use_state(|| false);
+
+    let on_shortcut = {
+        let help_open = help_open.clone();
+        Callback::from(move |shortcut: Shortcut| match shortcut {
+            // the run button is disabled while a request is running
+            Shortcut::Run if action_button_state.disabled() => {}
+            Shortcut::Run => actions.run.emit(()),
+            Shortcut::Save => actions.save.emit(()),
+            Shortcut::Format => actions.format.emit(()),
+            Shortcut::ToggleVersion => actions.toggle_version.emit(()),
+            Shortcut::Help => help_open.set(!*help_open),
+        })
+    };
+
+    // the listener is added once and calls the callback of the latest render
+    let latest_on_shortcut = use_mut_ref(Callback::noop);
+    *latest_on_shortcut.borrow_mut() = on_shortcut.clone();
+    {
+        let help_open = help_open.clone();
+        use_effect_with((), move |_| {
+            // the editor handles its shortcuts itself and stops them from bubbling up here
+            let listener = EventListener::new_with_options(
+                &gloo::utils::window(),
+                "keydown",
+                EventListenerOptions::enable_prevent_default(),
+                move |event| {
+                    let Some(event) = event.dyn_ref::<KeyboardEvent>() else {
+                        return;
+                    };
+                    if event.key() == "Escape" {
+                        help_open.set(false);
+                    } else if let Some(shortcut) = Shortcut::from_event(event) {
+                        event.prevent_default();
+                        latest_on_shortcut.borrow().emit(shortcut);
+                    }
+                },
+            );
+            move || drop(listener)
+        });
+    }
+
+    UseShortcutsHandle {
+        on_shortcut,
+        help_open,
+    }
+}
@@ -0,0 +1,197 @@
+//! Named projects kept in the browser's IndexedDB, one record per workspace.
+
+use js_sys::{JSON, Promise};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStoreParameters, IdbRequest, IdbTransactionMode};
+
+use crate::api::share::ShareSettings;
+use crate::utils::project::ProjectFiles;
+
+const DATABASE: &str = "yew-playground";
+const DATABASE_VERSION: u32 = 1;
+const STORE: &str = "workspaces";
+/// Version of the exported collection format.
+const EXPORT_FORMAT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub files: ProjectFiles,
+    #[serde(default)]
+    pub settings: ShareSettings,
+    /// Milliseconds since the epoch.
+    pub updated_at: f64,
+}
+
+impl Workspace {
+    pub fn new(
+        name: String,
+        version: String,
+        files: ProjectFiles,
+        settings: ShareSettings,
+    ) -> Self {
+        let now = js_sys::Date::now();
+        Self {
+            // unique enough for one browser, and sorts by creation
+            id: format!(
+                "{now:013}-{:08x}",
+                (js_sys::Math::random() * f64::from(u32::MAX)) as u32
+            ),
+            name,
+            version,
+            files,
+            settings,
+            updated_at: now,
+        }
+    }
+
+    /// A copy with its own id, e.g. to duplicate a workspace.
+    pub fn copy(&self, name: String) -> Self {
+        Self::new(
+            name,
+            self.version.clone(),
+            self.files.clone(),
+            self.settings.clone(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum WorkspaceError {
+    #[error("this browser does not allow saving projects, e.g. in private windows")]
+    Unavailable,
+    #[error("the browser failed to store the projects: {0}")]
+    Storage(String),
+    #[error("the file is not an exported project collection: {0}")]
+    Invalid(String),
+}
+
+impl From<JsValue> for WorkspaceError {
+    fn from(e: JsValue) -> Self {
+        let message = e
+            .dyn_ref::<js_sys::Error>()
+            .map(|it| String::from(it.message()))
+            .or_else(|| e.as_string())
+            .unwrap_or_else(|| format!("{e:?}"));
+        Self::Storage(message)
+    }
+}
+
+/// Waits for `request` to finish, with its result.
+async fn complete(request: &IdbRequest) -> Result<JsValue, WorkspaceError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        let onsuccess = {
+            let request = request.clone();
+            Closure::once_into_js(move || {
+                let result = request.result().unwrap_or_default();
+                let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+            })
+        };
+        let onerror = {
+            let request = request.clone();
+            Closure::once_into_js(move || {
+                let error = request.error().ok().flatten().map(JsValue::from);
+                let _ = reject.call1(&JsValue::UNDEFINED, &error.unwrap_or_default());
+            })
+        };
+        request.set_onsuccess(Some(onsuccess.unchecked_ref()));
+        request.set_onerror(Some(onerror.unchecked_ref()));
+    });
+    Ok(JsFuture::from(promise).await?)
+}
+
+async fn open() -> Result<IdbDatabase, WorkspaceError> {
+    let factory = web_sys::window()
+        .and_then(|it| it.indexed_db().ok().flatten())
+        .ok_or(WorkspaceError::Unavailable)?;
+    let request = factory.open_with_u32(DATABASE, DATABASE_VERSION)?;
+    // runs before the request succeeds, when the database is created
+    let onupgradeneeded = {
+        let request = request.clone();
+        Closure::once_into_js(move || {
+            if let Ok(db) = request.result() {
+                let params = IdbObjectStoreParameters::new();
+                params.set_key_path(&JsValue::from_str("id"));
+                let _ = db
+                    .unchecked_into::<IdbDatabase>()
+                    .create_object_store_with_optional_parameters(STORE, &params);
+            }
+        })
+    };
+    request.set_onupgradeneeded(Some(onupgradeneeded.unchecked_ref()));
+    Ok(complete(&request).await?.unchecked_into())
+}
+
+fn to_js(workspace: &Workspace) -> Result<JsValue, WorkspaceError> {
+    let json = serde_json::to_string(workspace).expect("workspaces serialize to JSON");
+    Ok(JSON::parse(&json)?)
+}
+
+/// All workspaces, the most recently updated first. Records that cannot be read are skipped.
+pub async fn list() -> Result<Vec<Workspace>, WorkspaceError> {
+    let db = open().await?;
+    let store = db.transaction_with_str(STORE)?.object_store(STORE)?;
+    let records: js_sys::Array = complete(&store.get_all()?).await?.unchecked_into();
+    let mut workspaces: Vec<Workspace> = records
+        .iter()
+        .filter_map(|record| {
+            let json = String::from(JSON::stringify(&record).ok()?);
+            serde_json::from_str(&json).ok()
+        })
+        .collect();
+    workspaces.sort_by(|a, b| b.updated_at.total_cmp(&a.updated_at));
+    Ok(workspaces)
+}
+
+/// Stores `workspaces`, replacing those with the same ids.
+pub async fn save(workspaces: &[Workspace]) -> Result<(), WorkspaceError> {
+    let db = open().await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+        .object_store(STORE)?;
+    for workspace in workspaces {
+        complete(&store.put(&to_js(workspace)?)?).await?;
+    }
+    Ok(())
+}
+
+pub async fn delete(id: &str) -> Result<(), WorkspaceError> {
+    let db = open().await?;
+    let store = db
+        .transaction_with_str_and_mode(STORE, IdbTransactionMode::Readwrite)?
+        .object_store(STORE)?;
+    complete(&store.delete(&JsValue::from_str(id))?).await?;
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize)]
+struct Collection {
+    format: u32,
+    workspaces: Vec<Workspace>,
+}
+
+/// `workspaces` as a JSON file that [`import`] reads back.
+pub fn export(workspaces: Vec<Workspace>) -> String {
+    serde_json::to_string_pretty(&Collection {
+        format: EXPORT_FORMAT,
+        workspaces,
+    })
+    .expect("workspaces serialize to JSON")
+}
+
+pub fn import(json: &str) -> Result<Vec<Workspace>, WorkspaceError> {
+    let collection: Collection =
+        serde_json::from_str(json).map_err(|e| WorkspaceError::Invalid(e.to_string()))?;
+    if collection.format != EXPORT_FORMAT {
+        return Err(WorkspaceError::Invalid(format!(
+            "format {} is not supported",
+            collection.format
+        )));
+    }
+    Ok(collection.workspaces)
+}
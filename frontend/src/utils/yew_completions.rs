@@ -0,0 +1,308 @@
+//! Yew snippets offered as completions in the code tab, independent of rust-analyzer. Bodies use
+//! Monaco's snippet syntax: `${1:name}` is a tab stop with a default, repeated ones are edited
+//! together, and `$0` is where the cursor ends up.
+
+use js_sys::{Function, Reflect};
+use monaco::sys::{IDisposable, languages};
+use serde::Serialize;
+use serde_json::json;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+
+use crate::utils::lsp::{MarkdownString, MonacoRange, to_js};
+
+/// Which template versions a snippet is offered for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Versions {
+    All,
+    Stable,
+    Next,
+}
+
+struct Template {
+    label: &'static str,
+    detail: &'static str,
+    versions: Versions,
+    body: &'static str,
+}
+
+const TEMPLATES: &[Template] = &[
+    Template {
+        label: "component",
+        detail: "#[component] function",
+        versions: Versions::All,
+        body: "#[component]
+fn ${1:Name}() -> Html {
+    html! {
+        $0
+    }
+}",
+    },
+    Template {
+        label: "component_props",
+        detail: "#[component] function with properties",
+        versions: Versions::All,
+        body: "#[derive(Properties, PartialEq)]
+pub struct ${1:Name}Props {
+    pub ${2:label}: ${3:AttrValue},
+}
+
+#[component]
+fn ${1:Name}(props: &${1:Name}Props) -> Html {
+    html! {
+        $0
+    }
+}",
+    },
+    Template {
+        label: "styled_component",
+        detail: "stylist #[styled_component] function",
+        versions: Versions::Stable,
+        body: "#[styled_component]
+fn ${1:Name}() -> Html {
+    html! {
+        <div class={css!(${2:display: flex;})}>
+            $0
+        </div>
+    }
+}",
+    },
+    Template {
+        label: "props",
+        detail: "Properties derive struct",
+        versions: Versions::All,
+        body: "#[derive(Properties, PartialEq)]
+pub struct ${1:Name}Props {
+    pub ${2:label}: ${3:AttrValue},
+    #[prop_or_default]
+    pub ${4:children}: ${5:Html},
+}",
+    },
+    Template {
+        label: "use_state",
+        detail: "use_state hook",
+        versions: Versions::All,
+        body: "let ${1:count} = use_state(|| ${2:0});",
+    },
+    Template {
+        label: "use_effect_with",
+        detail: "use_effect_with hook with cleanup",
+        versions: Versions::All,
+        body: "use_effect_with(${1:deps}, move |${2:deps}| {
+    $0
+    move || {}
+});",
+    },
+    Template {
+        label: "use_effect_once",
+        detail: "use_effect_with hook running on mount",
+        versions: Versions::All,
+        body: "use_effect_with((), move |_| {
+    $0
+});",
+    },
+    Template {
+        label: "html",
+        detail: "html! macro",
+        versions: Versions::All,
+        body: "html! {
+    $0
+}",
+    },
+    Template {
+        label: "div",
+        detail: "<div> with a class",
+        versions: Versions::All,
+        body: "<div class=\"${1}\">
+    $0
+</div>",
+    },
+    Template {
+        label: "button",
+        detail: "<button> with onclick",
+        versions: Versions::All,
+        body: "<button onclick={${1:onclick}}>{ \"${2:Click me}\" }</button>",
+    },
+    Template {
+        label: "input",
+        detail: "<input> with value and oninput",
+        versions: Versions::All,
+        body: "<input
+    type=\"${1:text}\"
+    value={(*${2:value}).clone()}
+    oninput={${3:oninput}}
+/>",
+    },
+    Template {
+        label: "for",
+        detail: "list rendering in html!",
+        versions: Versions::Stable,
+        body: "{ for ${1:items}.iter().map(|${2:item}| html! {
+    <li key={${2:item}.clone()}>{ ${2:item} }</li>
+}) }",
+    },
+    Template {
+        label: "for",
+        detail: "for loop in html!",
+        versions: Versions::Next,
+        body: "for ${2:item} in ${1:items}.iter() {
+    <li key={${2:item}.clone()}>{ ${2:item} }</li>
+}",
+    },
+    Template {
+        label: "callback",
+        detail: "Callback updating a state",
+        versions: Versions::All,
+        body: "let ${1:onclick} = {
+    let ${2:count} = ${2:count}.clone();
+    Callback::from(move |_: ${3:MouseEvent}| {
+        $0
+    })
+};",
+    },
+    Template {
+        label: "oninput",
+        detail: "Callback reading an input's value",
+        versions: Versions::All,
+        body: "let ${1:oninput} = {
+    let ${2:value} = ${2:value}.clone();
+    Callback::from(move |e: InputEvent| {
+        let input: web_sys::HtmlInputElement = e.target_unchecked_into();
+        ${2:value}.set(input.value().into());
+    })
+};",
+    },
+    Template {
+        label: "emit",
+        detail: "emit a Callback prop",
+        versions: Versions::All,
+        body: "props.${1:on_change}.emit(${2:value});",
+    },
+    Template {
+        label: "reform",
+        detail: "adapt a Callback prop to an event",
+        versions: Versions::All,
+        body: "props.${1:on_change}.reform(|_: ${2:MouseEvent}| ${3:()})",
+    },
+];
+
+fn templates_for(version: &str) -> impl Iterator<Item = &'static Template> {
+    let only = if version == "next" {
+        Versions::Next
+    } else {
+        Versions::Stable
+    };
+    TEMPLATES
+        .iter()
+        .filter(move |it| it.versions == Versions::All || it.versions == only)
+}
+
+/// The snippet as it is inserted with every tab stop at its default.
+fn preview(body: &str) -> String {
+    let mut preview = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(start) = rest.find('$') {
+        preview.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        if let Some(placeholder) = rest.strip_prefix('{') {
+            let end = placeholder.find('}').unwrap_or(placeholder.len());
+            let inner = &placeholder[..end];
+            preview.push_str(inner.split_once(':').map_or("", |(_, default)| default));
+            rest = placeholder.get(end + 1..).unwrap_or_default();
+        } else {
+            rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+        }
+    }
+    preview.push_str(rest);
+    preview
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Suggestion {
+    label: &'static str,
+    /// `CompletionItemKind.Snippet`
+    kind: u8,
+    detail: String,
+    documentation: MarkdownString,
+    insert_text: &'static str,
+    /// `CompletionItemInsertTextRule.InsertAsSnippet`
+    insert_text_rules: u8,
+    range: MonacoRange,
+}
+
+/// The Yew snippets for one template version, offered in every Rust model while this is alive.
+pub struct YewCompletions {
+    provider: IDisposable,
+    _closure: Closure<dyn Fn(JsValue, JsValue) -> JsValue>,
+}
+
+impl YewCompletions {
+    pub fn register(version: &str) -> Self {
+        let templates: Vec<_> = templates_for(version).collect();
+        let closure = Closure::<dyn Fn(JsValue, JsValue) -> JsValue>::new(
+            move |model: JsValue, position: JsValue| {
+                let Some(range) = word_range(&model, &position) else {
+                    return JsValue::NULL;
+                };
+                let suggestions: Vec<_> = templates
+                    .iter()
+                    .map(|template| Suggestion {
+                        label: template.label,
+                        kind: 27,
+                        detail: format!("{} (Yew snippet)", template.detail),
+                        documentation: MarkdownString {
+                            value: format!("```rust\n{}\n```", preview(template.body)),
+                        },
+                        insert_text: template.body,
+                        insert_text_rules: 4,
+                        range,
+                    })
+                    .collect();
+                to_js(&json!({ "suggestions": suggestions }))
+            },
+        );
+
+        let provider = js_sys::Object::new();
+        let _ = Reflect::set(
+            &provider,
+            &"provideCompletionItems".into(),
+            closure.as_ref(),
+        );
+        Self {
+            provider: languages::register_completion_item_provider(
+                "rust",
+                provider.unchecked_ref(),
+            ),
+            _closure: closure,
+        }
+    }
+}
+
+impl Drop for YewCompletions {
+    fn drop(&mut self) {
+        self.provider.dispose();
+    }
+}
+
+/// The range of the word being typed at `position`, which the snippet replaces.
+fn word_range(model: &JsValue, position: &JsValue) -> Option<MonacoRange> {
+    let number = |value: &JsValue, key: &str| {
+        Reflect::get(value, &key.into())
+            .ok()
+            .and_then(|it| it.as_f64())
+            .map(|it| it as u32)
+    };
+    let get_word = Reflect::get(model, &"getWordUntilPosition".into())
+        .ok()?
+        .dyn_into::<Function>()
+        .ok()?;
+    let word = get_word.call1(model, position).ok()?;
+    let line = number(position, "lineNumber")?;
+    Some(MonacoRange {
+        start_line_number: line,
+        start_column: number(&word, "startColumn")?,
+        end_line_number: line,
+        end_column: number(&word, "endColumn")?,
+    })
+}